@@ -1,5 +1,6 @@
 use criterion::{criterion_group, criterion_main, Criterion};
 use crossbeam_channel::unbounded;
+use kaksic::search::eval::Handcrafted;
 use kaksic::search::Searcher;
 use kaksic::{SearchCommand, SearchControl, SearchInfo};
 use shakmaty::fen::Fen;
@@ -13,13 +14,13 @@ fn search_positions_to_depth(positions: &Vec<Chess>, depth: u8) {
     let (cmd_tx, cmd_rx) = unbounded();
     let (info_tx, info_rx) = unbounded();
 
-    thread::spawn(|| Searcher::new(cmd_rx, info_tx).run());
+    thread::spawn(|| Searcher::new(cmd_rx, info_tx, Box::new(Handcrafted::default())).run());
 
     for position in positions {
         // send start signal
         cmd_tx
             .send(SearchCommand::Start {
-                position: position.clone(),
+                position: position.clone().into(),
                 control: SearchControl::ToDepth(depth),
             })
             .unwrap();
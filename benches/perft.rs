@@ -0,0 +1,37 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use kaksic::search::perft::perft;
+use shakmaty::fen::Fen;
+use shakmaty::{CastlingMode, Chess};
+use std::hint::black_box;
+use std::str::FromStr;
+use std::time::Duration;
+
+fn parse_fen(fen_str: &str) -> Chess {
+    Fen::from_str(fen_str)
+        .unwrap()
+        .into_position(CastlingMode::Standard)
+        .unwrap()
+}
+
+fn make_unmake_bench(c: &mut Criterion) {
+    let start = Chess::default();
+    let kiwipete =
+        parse_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+
+    c.bench_function("Perft 4 Start Position", |b| {
+        b.iter(|| perft(black_box(&start), 4))
+    });
+    c.bench_function("Perft 3 Kiwipete", |b| {
+        b.iter(|| perft(black_box(&kiwipete), 3))
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default()
+        .warm_up_time(Duration::from_secs(1))
+        .measurement_time(Duration::from_secs(5)).sample_size(10);
+    targets = make_unmake_bench
+}
+
+criterion_main!(benches);
@@ -1,7 +1,9 @@
 use shakmaty::fen::Fen;
 use shakmaty::{CastlingMode, Chess};
+use std::env;
 use std::fs::{self, File};
 use std::io::{BufRead, BufReader};
+use std::path::Path;
 use std::str::FromStr;
 
 fn main() {
@@ -32,4 +34,14 @@ fn main() {
 
     // Write filtered lines back to the file (overwriting it)
     fs::write(fens_path, filtered.join("\n") + "\n").expect("Failed to write filtered fens.txt");
+
+    // Embed the default network if there is one, otherwise embed an empty file
+    let net_path = Path::new("assets/net.nnue");
+    println!("cargo:rerun-if-changed=assets");
+    let embedded_path = Path::new(&env::var("OUT_DIR").unwrap()).join("net.nnue");
+    if net_path.exists() {
+        fs::copy(net_path, embedded_path).expect("Failed to embed net.nnue");
+    } else {
+        fs::write(embedded_path, []).expect("Failed to write empty net.nnue");
+    }
 }
@@ -0,0 +1,109 @@
+use kaksic::bot::book::{self, Entry};
+use kaksic::bot::pgn::Games;
+use shakmaty::Position;
+use std::collections::HashMap;
+use std::env;
+use std::fs::File;
+use std::io::BufWriter;
+
+// Parameters
+const DEFAULT_OUTPUT: &str = "book.bin";
+/// Plies from the start of each game that go into the book, as the default `BookDepth`
+const DEFAULT_PLIES: usize = 20;
+/// Moves played in fewer games are left out
+const DEFAULT_MIN_GAMES: u32 = 3;
+/// Moves scoring less than this percentage for the side playing them are left out
+const DEFAULT_MIN_SCORE: u32 = 40;
+
+/// Results of a move in a position, counted in half points for the side playing it
+#[derive(Default)]
+struct Stats {
+    games: u32,
+    half_points: u32,
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut files = Vec::new();
+    let mut output = DEFAULT_OUTPUT.to_string();
+    let mut plies = DEFAULT_PLIES;
+    let mut min_games = DEFAULT_MIN_GAMES;
+    let mut min_score = DEFAULT_MIN_SCORE;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .unwrap_or_else(|| panic!("Missing value for {name}"))
+                .clone()
+        };
+        match arg.as_str() {
+            "--output" => output = value(arg),
+            "--plies" => plies = value(arg).parse().expect("Invalid ply count"),
+            "--min-games" => min_games = value(arg).parse().expect("Invalid game count"),
+            "--min-score" => min_score = value(arg).parse().expect("Invalid score"),
+            _ => files.push(arg.clone()),
+        }
+    }
+    if files.is_empty() {
+        eprintln!("Usage: book <games.pgn>... [--output book.bin] [--plies n] [--min-games n]");
+        eprintln!("                           [--min-score percent]");
+        eprintln!(
+            "Builds a Polyglot book from the first plies of the finished games, keeping moves"
+        );
+        eprintln!("played often enough that score well enough for the side playing them.");
+        std::process::exit(1);
+    }
+
+    let mut stats: HashMap<(u64, u16), Stats> = HashMap::new();
+    let mut games = 0;
+    // Unreadable and unfinished games are left out
+    let mut skipped = 0;
+    for path in &files {
+        let file = File::open(path).expect("Failed to open games file");
+        let mut reader = Games::new(file);
+        for game in &mut reader {
+            let game = game.expect("Failed to read games file");
+            games += 1;
+            for (position, mv) in game.replay().take(plies) {
+                let entry = stats
+                    .entry((book::key(&position), book::encode_move(mv)))
+                    .or_default();
+                entry.games += 1;
+                entry.half_points += match game.winner {
+                    Some(winner) if winner == position.turn() => 2,
+                    Some(_) => 0,
+                    None => 1,
+                };
+            }
+        }
+        skipped += reader.skipped();
+    }
+    println!(
+        "Read {} games with {} moves, skipped {}",
+        games,
+        stats.len(),
+        skipped
+    );
+
+    let mut entries: Vec<Entry> = stats
+        .into_iter()
+        .filter(|(_, stats)| {
+            stats.games >= min_games && stats.half_points * 50 >= min_score * stats.games
+        })
+        .map(|((key, mv), stats)| Entry {
+            key,
+            mv,
+            weight: stats.half_points.clamp(1, u16::MAX as u32) as u16,
+            learn: 0,
+        })
+        .collect();
+
+    let file = File::create(&output).expect("Failed to create book");
+    book::write(&mut BufWriter::new(file), &mut entries).expect("Failed to write book");
+    println!(
+        "Book with {} entries written to '{}'",
+        entries.len(),
+        output
+    );
+}
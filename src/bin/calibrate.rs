@@ -0,0 +1,115 @@
+use kaksic::search::skill::Skill;
+use kaksic::search::{Limits, SearchOptions};
+use kaksic::Engine;
+use rand::seq::IndexedRandom;
+use shakmaty::{Board, Chess, Color, KnownOutcome, Outcome, Position};
+use std::env;
+
+// Parameters
+/// Enough for an error bar of about 35 Elo either way
+const DEFAULT_GAMES: usize = 400;
+/// Deepest search of either side, a weakened engine searches less deeply still
+const SEARCH_DEPTH: u8 = 4;
+/// Random moves at the start of each game, so the games differ
+const RANDOM_PLIES: usize = 4;
+/// Games still going after this many moves are drawn
+const MAX_PLIES: usize = 200;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() {
+        eprintln!("Usage: calibrate <skill level>... [--games n]");
+        eprintln!("Plays each skill level against the full strength engine and estimates the");
+        eprintln!("rating difference, for the calibration in `search::skill`.");
+        std::process::exit(1);
+    }
+
+    let mut games = DEFAULT_GAMES;
+    let mut levels = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--games" {
+            games = args
+                .next()
+                .and_then(|games| games.parse().ok())
+                .expect("Invalid game count");
+        } else {
+            levels.push(arg.parse::<u8>().expect("Invalid skill level"));
+        }
+    }
+
+    for level in levels {
+        let mut weak = Engine::new();
+        weak.set_options(SearchOptions {
+            skill: Skill::new(level),
+            ..Default::default()
+        });
+        let mut full = Engine::new();
+
+        // Colors alternate, the scores are the weakened engine's
+        let scores: Vec<f64> = (0..games)
+            .map(|game| {
+                if game % 2 == 0 {
+                    play(&mut weak, &mut full)
+                } else {
+                    1.0 - play(&mut full, &mut weak)
+                }
+            })
+            .collect();
+        let score: f64 = scores.iter().sum();
+
+        // 95% confidence interval of the mean score, converted to ratings
+        let mean = score / games as f64;
+        let variance = scores.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / games as f64;
+        let margin = 1.96 * (variance / games as f64).sqrt();
+        println!(
+            "Skill level {level}: {score}/{games}, {:+.0} Elo ({:+.0} to {:+.0})",
+            elo_difference(mean),
+            elo_difference(mean - margin),
+            elo_difference(mean + margin),
+        );
+    }
+}
+
+/// Play a game, returning white's score
+fn play(white: &mut Engine, black: &mut Engine) -> f64 {
+    let mut rng = rand::rng();
+    let mut position = Chess::default();
+    let mut boards: Vec<Board> = Vec::new();
+
+    for ply in 0..MAX_PLIES {
+        if position.is_game_over() {
+            return match position.outcome() {
+                Outcome::Known(KnownOutcome::Decisive { winner }) => winner.fold_wb(1.0, 0.0),
+                _ => 0.5,
+            };
+        }
+        // Fifty moves and threefold repetition, counting boards only
+        let board = position.board().clone();
+        if position.halfmoves() >= 100 || boards.iter().filter(|&b| *b == board).count() >= 2 {
+            return 0.5;
+        }
+        boards.push(board);
+
+        let mv = if ply < RANDOM_PLIES {
+            *position.legal_moves().choose(&mut rng).unwrap()
+        } else {
+            let engine = match position.turn() {
+                Color::White => &mut *white,
+                Color::Black => &mut *black,
+            };
+            engine
+                .analyse(&position, Limits::depth(SEARCH_DEPTH))
+                .best_move
+                .expect("game is not over")
+        };
+        position.play_unchecked(mv);
+    }
+    0.5
+}
+
+/// Rating difference giving an expected score, limited to keep it finite
+fn elo_difference(score: f64) -> f64 {
+    let score = score.clamp(0.01, 0.99);
+    -400.0 * (1.0 / score - 1.0).log10()
+}
@@ -0,0 +1,40 @@
+use kaksic::search::perft;
+use shakmaty::fen::Fen;
+use shakmaty::{CastlingMode, Chess};
+use std::env;
+use std::str::FromStr;
+use std::time::Instant;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() {
+        eprintln!("Usage: perft <depth> [fen]");
+        eprintln!("Prints the number of leaf nodes below each legal move of the position,");
+        eprintln!("which is the start position if no FEN is given.");
+        std::process::exit(1);
+    }
+    let depth: u8 = args[0].parse().expect("Invalid depth");
+
+    // Chess960 positions are recognized by their castling rights
+    let (position, mode) = match args.get(1..).filter(|fen| !fen.is_empty()) {
+        // The FEN may be given as a single argument or split over several
+        Some(fen) => {
+            let fen = Fen::from_str(&fen.join(" ")).expect("Invalid FEN");
+            let mode = CastlingMode::detect(fen.as_setup());
+            let position: Chess = fen.into_position(mode).expect("Illegal position");
+            (position, mode)
+        }
+        None => (Chess::default(), CastlingMode::Standard),
+    };
+
+    let start = Instant::now();
+    let result = perft::divide(&position, depth, mode);
+    let elapsed = start.elapsed();
+
+    println!("{result}");
+    println!(
+        "Time: {} ms, {} nodes per second",
+        elapsed.as_millis(),
+        (result.nodes() as f64 / elapsed.as_secs_f64()) as u64
+    );
+}
@@ -1,6 +1,9 @@
+use chrono::Local;
+use kaksic::bot::crash::{Recent, CRASH_FILE};
 use std::env;
+use std::fs::OpenOptions;
 use std::io::{self, Read, Write};
-use std::process::{Command, Stdio};
+use std::process::{Command, ExitStatus, Stdio};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
@@ -51,10 +54,14 @@ fn main() {
     // Channel to signal quit
     let (quit_tx, quit_rx) = std::sync::mpsc::channel();
 
+    // Last commands sent to the child, for the report if it crashes
+    let recent = Arc::new(Mutex::new(Recent::default()));
+
     // Thread to forward stdin to child, and detect "quit"
     {
         let quit_tx = quit_tx.clone();
         let child_stdin_arc = Arc::clone(&child_stdin_arc);
+        let recent = Arc::clone(&recent);
         thread::spawn(move || {
             let stdin = io::stdin();
             let mut handle = stdin.lock();
@@ -63,8 +70,7 @@ fn main() {
                 match handle.read(&mut buffer) {
                     Ok(0) => break, // EOF
                     Ok(n) => {
-                        let input_str = String::from_utf8_lossy(&buffer[..n]).to_lowercase();
-                        if input_str.contains("quit") {
+                        if recent.lock().unwrap().remember(&buffer[..n]) {
                             let _ = quit_tx.send(());
                             // Forward "quit" to child before killing
                             let _ = child_stdin_arc.lock().unwrap().write_all(&buffer[..n]);
@@ -108,11 +114,13 @@ fn main() {
     }
 
     // Main thread: wait for quit or child exit
+    let mut killed = false;
     let status = loop {
         // If quit signal received, kill child
         if let Ok(_) = quit_rx.try_recv() {
             let mut child = child_arc.lock().unwrap();
             let _ = child.kill();
+            killed = true;
             let status = child.wait().expect("Failed to wait on child process");
             break status;
         }
@@ -137,5 +145,31 @@ fn main() {
         }
     };
 
+    if !killed && !status.success() {
+        report_crash(status, &recent.lock().unwrap());
+    }
+
     std::process::exit(status.code().unwrap_or(1));
 }
+
+/// Tell the user and the user interface that the engine died, also when it could not write a
+/// report itself, for example when it was killed by a signal
+fn report_crash(status: ExitStatus, recent: &Recent) {
+    let mut report = format!(
+        "------ Engine exited abnormally at {} ({status}) ------\nLast commands:\n",
+        Local::now().format("%Y-%m-%d %H:%M:%S%.3f")
+    );
+    for command in recent.commands() {
+        report.push_str(&format!("  {command}\n"));
+    }
+
+    eprint!("{report}");
+    if let Ok(mut file) = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(CRASH_FILE)
+    {
+        let _ = write!(file, "{report}");
+    }
+    println!("info string Engine crashed ({status}), see {CRASH_FILE}");
+}
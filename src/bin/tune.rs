@@ -0,0 +1,106 @@
+use kaksic::search::eval::{EvalParams, DEFAULT_PARAMS};
+use kaksic::search::fit::{parse_line, Adam};
+use kaksic::search::tune::{error, find_scaling_constant, gradient, quiesce, Entry};
+use shakmaty::Position;
+use std::env;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+
+// Parameters
+const DEFAULT_EPOCHS: usize = 2000;
+const LEARNING_RATE: f64 = 1.0;
+const REPORT_INTERVAL: usize = 100;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() {
+        eprintln!("Usage: tune <positions file> [output file] [epochs]");
+        eprintln!("Each line of the positions file holds a FEN followed by the game result,");
+        eprintln!("e.g. '1-0', '0-1', '1/2-1/2' or '[1.0]', '[0.5]', '[0.0]'.");
+        std::process::exit(1);
+    }
+    let data_path = &args[0];
+    let output_path = args.get(1).map_or("tuned_params.rs", String::as_str);
+    let epochs = args.get(2).map_or(DEFAULT_EPOCHS, |epochs| {
+        epochs.parse().expect("Invalid epoch count")
+    });
+
+    // Load and resolve positions
+    let entries = load_entries(data_path);
+    if entries.is_empty() {
+        eprintln!("No usable positions in '{}'", data_path);
+        std::process::exit(1);
+    }
+    println!("Loaded {} positions", entries.len());
+
+    let mut params = DEFAULT_PARAMS;
+    let mut weights: Vec<f64> = params
+        .weights_mut()
+        .into_iter()
+        .map(|w| *w as f64)
+        .collect();
+
+    let k = find_scaling_constant(&entries, &weights);
+    println!("Scaling constant K = {:.4}", k);
+    println!("Initial error: {:.6}", error(&entries, &weights, k));
+
+    let mut adam = Adam::new(weights.len(), LEARNING_RATE);
+    for epoch in 1..=epochs {
+        let gradient = gradient(&entries, &weights, k);
+        adam.step(&mut weights, &gradient);
+
+        if epoch % REPORT_INTERVAL == 0 || epoch == epochs {
+            println!("Epoch {epoch}: error {:.6}", error(&entries, &weights, k));
+            write_params(&mut params, &weights, output_path);
+        }
+    }
+
+    println!("Tuned parameters written to '{}'", output_path);
+}
+
+/// Reads labeled positions and replaces each by the quiet position at the end of its quiescence search
+fn load_entries(path: &str) -> Vec<Entry> {
+    let file = File::open(path).expect("Failed to open positions file");
+    let reader = BufReader::new(file);
+
+    let mut entries = Vec::new();
+    let mut skipped = 0;
+
+    for line in reader.lines() {
+        let line = line.expect("Failed to read positions file");
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let Some((position, result)) = parse_line(&line) else {
+            skipped += 1;
+            continue;
+        };
+
+        // Positions in check can't be resolved by a captures-only search
+        if position.is_check() {
+            skipped += 1;
+            continue;
+        }
+        let (_, quiet) = quiesce(&position, -i32::MAX, i32::MAX);
+        if quiet.is_check() || quiet.is_game_over() {
+            skipped += 1;
+            continue;
+        }
+
+        entries.push(Entry::new(&quiet, result));
+    }
+
+    if skipped > 0 {
+        println!("Skipped {} lines", skipped);
+    }
+
+    entries
+}
+
+fn write_params(params: &mut EvalParams, weights: &[f64], path: &str) {
+    for (weight, tuned) in params.weights_mut().into_iter().zip(weights) {
+        *weight = tuned.round() as i32;
+    }
+    fs::write(path, params.to_string()).expect("Failed to write tuned parameters");
+}
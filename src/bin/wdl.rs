@@ -0,0 +1,238 @@
+use kaksic::bot::pgn::Games;
+use kaksic::search::eval;
+use kaksic::search::fit::{parse_line, Adam};
+use kaksic::search::wdl::{self, WdlModel};
+use kaksic::search::Limits;
+use kaksic::Engine;
+use rand::seq::IndexedRandom;
+use shakmaty::{Board, Chess, KnownOutcome, Outcome, Position};
+use std::env;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+
+// Parameters
+const DEFAULT_EPOCHS: usize = 2000;
+const LEARNING_RATE: f64 = 1.0;
+const REPORT_INTERVAL: usize = 100;
+/// Opening moves left out of games, they say more about the opening book than the position
+const SKIP_PLIES: usize = 8;
+/// Scores beyond this are left out, the game is decided and the exact score means little
+const MAX_SCORE: i32 = 2000;
+/// Depth both sides of a self-play game search to, after the opening moves played at random
+const SELFPLAY_DEPTH: u8 = 2;
+/// Self-play games still going after this many moves are drawn
+const MAX_PLIES: usize = 200;
+/// Step used to estimate the gradient
+const GRADIENT_STEP: f64 = 1e-3;
+/// Model the fit starts from: a win rate of one half at 2 pawns, whatever the material
+const INITIAL_MODEL: WdlModel = WdlModel {
+    a: [0.0, 0.0, 0.0, 200.0],
+    b: [0.0, 0.0, 0.0, 100.0],
+};
+
+/// A position seen in a game, from the point of view of the side to move
+struct Sample {
+    score: i32,
+    material: u32,
+    /// 1.0 for a win, 0.5 for a draw, 0.0 for a loss
+    result: f64,
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() {
+        eprintln!(
+            "Usage: wdl <games.pgn | positions file | --selfplay games> [output file] [epochs]"
+        );
+        eprintln!("Fits the win/draw/loss model to the results of the games. A positions file");
+        eprintln!("holds a FEN and the game result per line, e.g. '1-0', '0-1' or '1/2-1/2'.");
+        eprintln!("With --selfplay the engine plays the games against itself first.");
+        std::process::exit(1);
+    }
+    // Self-play takes the number of games in place of a data file
+    let selfplay = args[0] == "--selfplay";
+    let args = if selfplay { &args[1..] } else { &args[..] };
+    let data_path = args.first().expect("Invalid game count");
+    let output_path = args.get(1).map_or("wdl_model.rs", String::as_str);
+    let epochs = args.get(2).map_or(DEFAULT_EPOCHS, |epochs| {
+        epochs.parse().expect("Invalid epoch count")
+    });
+
+    let samples = if selfplay {
+        play_games(data_path.parse().expect("Invalid game count"))
+    } else if data_path.ends_with(".pgn") {
+        load_games(data_path)
+    } else {
+        load_positions(data_path)
+    };
+    if samples.is_empty() {
+        eprintln!("No usable positions in '{}'", data_path);
+        std::process::exit(1);
+    }
+    println!("Loaded {} positions", samples.len());
+
+    let mut params: Vec<f64> = INITIAL_MODEL
+        .a
+        .iter()
+        .chain(&INITIAL_MODEL.b)
+        .copied()
+        .collect();
+    println!("Initial error: {:.6}", error(&samples, &model(&params)));
+
+    let mut adam = Adam::new(params.len(), LEARNING_RATE);
+    for epoch in 1..=epochs {
+        let gradient = gradient(&samples, &params);
+        adam.step(&mut params, &gradient);
+
+        if epoch % REPORT_INTERVAL == 0 || epoch == epochs {
+            let model = model(&params);
+            println!("Epoch {epoch}: error {:.6}", error(&samples, &model));
+            fs::write(output_path, model.to_string()).expect("Failed to write model");
+        }
+    }
+
+    println!("Model written to '{}'", output_path);
+}
+
+fn model(params: &[f64]) -> WdlModel {
+    WdlModel {
+        a: params[..4].try_into().unwrap(),
+        b: params[4..].try_into().unwrap(),
+    }
+}
+
+/// Mean negative log likelihood of the game results
+fn error(samples: &[Sample], model: &WdlModel) -> f64 {
+    let total: f64 = samples
+        .iter()
+        .map(|sample| {
+            let win = model.win_rate(sample.score, sample.material);
+            let loss = model.win_rate(-sample.score, sample.material);
+            let probability = match sample.result {
+                1.0 => win,
+                0.0 => loss,
+                _ => 1.0 - win - loss,
+            };
+            -probability.max(1e-12).ln()
+        })
+        .sum();
+    total / samples.len() as f64
+}
+
+/// Central differences, the model is cheap to evaluate and has few parameters
+fn gradient(samples: &[Sample], params: &[f64]) -> Vec<f64> {
+    (0..params.len())
+        .map(|i| {
+            let mut up = params.to_vec();
+            let mut down = params.to_vec();
+            up[i] += GRADIENT_STEP;
+            down[i] -= GRADIENT_STEP;
+            (error(samples, &model(&up)) - error(samples, &model(&down))) / (2.0 * GRADIENT_STEP)
+        })
+        .collect()
+}
+
+/// The sample for a position, if its score is meaningful
+fn sample(position: &Chess, white_result: f64) -> Option<Sample> {
+    if position.is_check() || position.is_game_over() {
+        return None;
+    }
+    let score = eval::eval(position);
+    (score.abs() <= MAX_SCORE).then(|| Sample {
+        score,
+        material: wdl::material(position),
+        result: position.turn().fold_wb(white_result, 1.0 - white_result),
+    })
+}
+
+/// Reads lines of a FEN followed by the game result from white's point of view
+fn load_positions(path: &str) -> Vec<Sample> {
+    let file = File::open(path).expect("Failed to open positions file");
+    let mut samples = Vec::new();
+    let mut skipped = 0;
+
+    for line in BufReader::new(file).lines() {
+        let line = line.expect("Failed to read positions file");
+        match parse_line(&line).and_then(|(position, result)| sample(&position, result)) {
+            Some(sample) => samples.push(sample),
+            None => skipped += 1,
+        }
+    }
+
+    if skipped > 0 {
+        println!("Skipped {} lines", skipped);
+    }
+    samples
+}
+
+/// Reads every position of every finished game
+fn load_games(path: &str) -> Vec<Sample> {
+    let file = File::open(path).expect("Failed to open games file");
+    let mut reader = Games::new(file);
+    let mut samples = Vec::new();
+    let mut games = 0;
+
+    for game in &mut reader {
+        let game = game.expect("Failed to read games file");
+        let result = game.white_result();
+        samples.extend(
+            game.positions()
+                .skip(SKIP_PLIES)
+                .filter_map(|position| sample(&position, result)),
+        );
+        games += 1;
+    }
+
+    println!("Read {} games, skipped {}", games, reader.skipped());
+    samples
+}
+
+/// Plays games of the engine against itself, both sides searching to `SELFPLAY_DEPTH` after
+/// `SKIP_PLIES` random moves, and reads every position after the random moves
+fn play_games(games: usize) -> Vec<Sample> {
+    let mut rng = rand::rng();
+    let mut engine = Engine::new();
+    let mut samples = Vec::new();
+
+    for _ in 0..games {
+        let mut position = Chess::default();
+        let mut positions = Vec::new();
+        let mut boards: Vec<Board> = Vec::new();
+
+        let mut result = 0.5;
+        for ply in 0..MAX_PLIES {
+            if position.is_game_over() {
+                if let Outcome::Known(KnownOutcome::Decisive { winner }) = position.outcome() {
+                    result = winner.fold_wb(1.0, 0.0);
+                }
+                break;
+            }
+            // Fifty moves and threefold repetition, counting boards only
+            let board = position.board().clone();
+            if position.halfmoves() >= 100 || boards.iter().filter(|&b| *b == board).count() >= 2 {
+                break;
+            }
+            boards.push(board);
+
+            let mv = if ply < SKIP_PLIES {
+                *position.legal_moves().choose(&mut rng).unwrap()
+            } else {
+                positions.push(position.clone());
+                engine
+                    .analyse(&position, Limits::depth(SELFPLAY_DEPTH))
+                    .best_move
+                    .expect("game is not over")
+            };
+            position.play_unchecked(mv);
+        }
+
+        samples.extend(
+            positions
+                .iter()
+                .filter_map(|position| sample(position, result)),
+        );
+    }
+
+    println!("Played {} games", games);
+    samples
+}
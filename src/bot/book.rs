@@ -0,0 +1,156 @@
+//! Opening books in the Polyglot format.
+//!
+//! A book is a file of 16 byte entries sorted by position key: the key, a move, its weight and
+//! a learn value, all big-endian. The keys are Polyglot's Zobrist hashes, which are the ones
+//! shakmaty computes for standard chess.
+
+use rand::RngExt;
+use shakmaty::zobrist::Zobrist64;
+use shakmaty::{EnPassantMode, Move, Position, Role, Square};
+use std::fs;
+use std::io::{self, Write};
+
+/// Size of an entry in a book file
+const ENTRY_BYTES: usize = 16;
+
+/// How a move is chosen among those the book has for a position
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Selection {
+    /// The move with the highest weight
+    Best,
+    /// A random move, with chances in proportion to the weights
+    Weighted,
+}
+
+/// A move stored for a position
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Entry {
+    pub key: u64,
+    /// The move in Polyglot's encoding, see `encode_move`
+    pub mv: u16,
+    pub weight: u16,
+    pub learn: u32,
+}
+
+/// Entries of a book, sorted by key
+pub struct Book {
+    entries: Vec<Entry>,
+}
+
+impl Book {
+    /// Read a book file
+    pub fn open(path: &str) -> io::Result<Book> {
+        Book::from_bytes(&fs::read(path)?)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Book> {
+        if !bytes.len().is_multiple_of(ENTRY_BYTES) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("size {} is not a multiple of {ENTRY_BYTES}", bytes.len()),
+            ));
+        }
+
+        let mut entries: Vec<Entry> = bytes
+            .chunks_exact(ENTRY_BYTES)
+            .map(|chunk| Entry {
+                key: u64::from_be_bytes(chunk[0..8].try_into().unwrap()),
+                mv: u16::from_be_bytes(chunk[8..10].try_into().unwrap()),
+                weight: u16::from_be_bytes(chunk[10..12].try_into().unwrap()),
+                learn: u32::from_be_bytes(chunk[12..16].try_into().unwrap()),
+            })
+            .collect();
+        // Books should be sorted already, lookups depend on it
+        entries.sort_by_key(|entry| entry.key);
+
+        Ok(Book { entries })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Legal moves the book has for `position`, with their weights
+    pub fn moves<P: Position>(&self, position: &P) -> Vec<(Move, u16)> {
+        let key = key(position);
+        let start = self.entries.partition_point(|entry| entry.key < key);
+        let legal_moves = position.legal_moves();
+
+        self.entries[start..]
+            .iter()
+            .take_while(|entry| entry.key == key)
+            .filter_map(|entry| {
+                legal_moves
+                    .iter()
+                    .find(|&&mv| encode_move(mv) == entry.mv)
+                    .map(|&mv| (mv, entry.weight))
+            })
+            .collect()
+    }
+
+    /// Choose a move for `position`, if the book has one with a weight
+    pub fn choose<P: Position>(&self, position: &P, selection: Selection) -> Option<Move> {
+        let moves = self.moves(position);
+        let total: u32 = moves.iter().map(|&(_, weight)| weight as u32).sum();
+        if total == 0 {
+            return None;
+        }
+
+        match selection {
+            Selection::Best => moves
+                .iter()
+                .max_by_key(|&&(_, weight)| weight)
+                .map(|&(mv, _)| mv),
+            Selection::Weighted => {
+                let mut choice = rand::rng().random_range(0..total);
+                moves.into_iter().find_map(|(mv, weight)| {
+                    if choice < weight as u32 {
+                        Some(mv)
+                    } else {
+                        choice -= weight as u32;
+                        None
+                    }
+                })
+            }
+        }
+    }
+}
+
+/// Polyglot key of a standard chess position
+pub fn key<P: Position>(position: &P) -> u64 {
+    position.zobrist_hash::<Zobrist64>(EnPassantMode::Legal).0
+}
+
+/// Polyglot's encoding of a move: target file and rank, origin file and rank, then the
+/// promotion, three bits each. Castling moves go from the king to the rook.
+pub fn encode_move(mv: Move) -> u16 {
+    let square = |square: Square| (square.rank() as u16) << 3 | square.file() as u16;
+    let (from, to) = match mv {
+        Move::Castle { king, rook } => (king, rook),
+        _ => (mv.from().expect("no drops in standard chess"), mv.to()),
+    };
+    let promotion = match mv.promotion() {
+        Some(Role::Knight) => 1,
+        Some(Role::Bishop) => 2,
+        Some(Role::Rook) => 3,
+        Some(Role::Queen) => 4,
+        _ => 0,
+    };
+    promotion << 12 | square(from) << 6 | square(to)
+}
+
+/// Write entries as a book, sorted by key as lookups require and by weight within a position
+pub fn write<W: Write>(writer: &mut W, entries: &mut [Entry]) -> io::Result<()> {
+    entries.sort_by_key(|entry| (entry.key, std::cmp::Reverse(entry.weight)));
+    for entry in entries.iter() {
+        writer.write_all(&entry.key.to_be_bytes())?;
+        writer.write_all(&entry.mv.to_be_bytes())?;
+        writer.write_all(&entry.weight.to_be_bytes())?;
+        writer.write_all(&entry.learn.to_be_bytes())?;
+    }
+    Ok(())
+}
@@ -1,37 +1,104 @@
+use crate::bot::book::{Book, Selection};
+use crate::bot::crash;
+use crate::bot::display::Display;
+use crate::bot::input::{EngineCommand, Input};
+use crate::bot::log::{FileLogger, Format, Level, Logger};
+use crate::bot::options::{self, OptionValue, UciOption, OPTIONS};
+use crate::bot::position::setup_position;
+use crate::search::bench::{self, BENCH_DEPTH};
+use crate::search::eval::{self, Handcrafted};
+use crate::search::mcts::Mcts;
+use crate::search::negamax::Negamax;
+use crate::search::nnue::{Network, NnueState};
+use crate::search::perft;
+use crate::search::skill::{Skill, MAX_LEVEL, MIN_ELO};
+use crate::search::tablebase::Tablebases;
+use crate::search::wdl::{self, WDL_MODEL};
+use crate::search::{Evaluator, SearchAlgorithm, SearchOptions};
 use crate::{SearchCommand, SearchControl, SearchInfo, SEARCH_TIME_MS};
 use chrono::Local;
 use crossbeam_channel::{select, Receiver, Sender};
-use shakmaty::{CastlingMode, Chess, Position};
+use shakmaty::fen::Fen;
+use shakmaty::variant::{Variant, VariantPosition};
+use shakmaty::{CastlingMode, EnPassantMode, Move, Position};
 use shakmaty_uci::{UciInfo, UciInfoScore, UciMessage, UciMove, UciSearchControl};
-use std::{fs::OpenOptions, io::Write};
+use std::io::{self, Write};
+use std::{sync::Arc, time::Instant};
 
 /// Handles incoming commands, sends outgoing messages and produces runtime logs.
-pub struct Controller {
-    input_rx: Receiver<UciMessage>,
+///
+/// Messages are written to `output`, standard output when run as an engine.
+pub struct Controller<W: Write = io::Stdout, L: Logger = FileLogger> {
+    input_rx: Receiver<Input>,
     cmd_tx: Sender<SearchCommand>,
     info_rx: Receiver<SearchInfo>,
-    position: Chess,
-    log_file: &'static str,
+    output: W,
+    logger: L,
+    position: VariantPosition,
+    network: Option<Arc<Network>>,
+    use_nnue: bool,
+    search_options: SearchOptions,
+    move_overhead_ms: u64,
+    /// How castling moves are written, depending on `UCI_Chess960`
+    castling_mode: CastlingMode,
+    /// Rules of the game, set by `UCI_Variant`
+    variant: Variant,
+    /// Whether info lines include win, draw and loss chances, set by `UCI_ShowWDL`
+    show_wdl: bool,
+    /// Material of the position being searched, for the win, draw and loss chances of its info
+    /// lines, which may arrive after the next `position` command
+    search_material: u32,
+    /// `Skill Level`, used unless strength is limited by rating
+    skill_level: u8,
+    /// `UCI_LimitStrength` and `UCI_Elo`
+    limit_strength: bool,
+    elo: u16,
+    /// Opening book, used while `OwnBook` is set and the game is shorter than `book_depth` plies
+    book: Option<Book>,
+    own_book: bool,
+    book_depth: u32,
+    book_selection: Selection,
 }
 
-impl Controller {
+impl<W: Write, L: Logger> Controller<W, L> {
     pub fn new(
-        input_rx: Receiver<UciMessage>,
+        input_rx: Receiver<Input>,
         cmd_tx: Sender<SearchCommand>,
         info_rx: Receiver<SearchInfo>,
-        log_file: &'static str,
+        output: W,
+        logger: L,
     ) -> Self {
         let controller = Controller {
             input_rx,
             cmd_tx,
             info_rx,
-            position: Chess::default(),
-            log_file,
+            output,
+            logger,
+            position: VariantPosition::default(),
+            network: Network::embedded().map(Arc::new),
+            use_nnue: true,
+            search_options: SearchOptions::default(),
+            move_overhead_ms: 10,
+            castling_mode: CastlingMode::Standard,
+            variant: Variant::Chess,
+            show_wdl: false,
+            search_material: 0,
+            skill_level: MAX_LEVEL,
+            limit_strength: false,
+            elo: MIN_ELO,
+            book: None,
+            own_book: false,
+            book_depth: 20,
+            book_selection: Selection::Weighted,
         };
 
         let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S");
-        controller.log("");
-        controller.log(&format!("------ Engine started at {} ------", timestamp));
+        controller.log(
+            Level::Info,
+            &format!("------ Engine started at {} ------", timestamp),
+        );
+
+        controller.update_evaluation();
 
         controller
     }
@@ -41,11 +108,16 @@ impl Controller {
         loop {
             select! {
                 recv(self.input_rx) -> cmd => {
-                    let cmd = cmd.unwrap();
-                    self.log(&format!(" IN: '{}'", &cmd));
+                    // The user interface closed the input, which ends the session like `quit`
+                    let Ok(cmd) = cmd else { break };
+                    self.log(Level::Info, &format!(" IN: '{}'", &cmd));
+                    crash::record_command(&cmd.to_string());
                     if self.handle_input(cmd) {
                         break;
                     }
+                    crash::record_position(
+                        Fen::from_position(&self.position, EnPassantMode::Legal).to_string(),
+                    );
                 }
 
                 recv(self.info_rx) -> info => self.handle_info(info.unwrap()),
@@ -54,13 +126,69 @@ impl Controller {
     }
 
     /// Sends an outbound message
-    fn send(&self, msg: UciMessage) {
-        println!("{msg}");
-        self.log(&format!("OUT: '{}'", msg));
+    fn send(&mut self, msg: UciMessage) {
+        self.write_line(&msg.to_string());
+    }
+
+    /// Sends free-form text that is not a UCI message, line by line
+    fn send_text(&mut self, text: &str) {
+        for line in text.lines() {
+            self.write_line(line);
+        }
+    }
+
+    fn write_line(&mut self, line: &str) {
+        writeln!(self.output, "{line}")
+            .and_then(|_| self.output.flush())
+            .expect("failed to write to the user interface");
+        self.log(Level::Info, &format!("OUT: '{}'", line));
     }
 
     /// Handles incoming commands from user interface
-    fn handle_input(&mut self, message: UciMessage) -> bool {
+    fn handle_input(&mut self, input: Input) -> bool {
+        match input {
+            Input::Uci(message) => return self.handle_uci(message),
+            Input::Engine(cmd) => self.handle_engine_command(cmd),
+
+            // Let the user know the command was not ignored by accident
+            Input::Unknown(line) => {
+                self.log(Level::Warn, &format!("ERR: 'unknown command: {}'", line));
+                self.send_text(&format!("info string unknown command {line}"));
+            }
+        }
+        false
+    }
+
+    /// Handles commands that are not part of the UCI protocol
+    fn handle_engine_command(&mut self, cmd: EngineCommand) {
+        match cmd {
+            // Print evaluation breakdown of the current position
+            EngineCommand::Eval => self.send_text(&eval::trace(&self.position).to_string()),
+
+            // Count leaf nodes below each legal move
+            EngineCommand::Perft(depth) => {
+                let start = Instant::now();
+                self.send_text(
+                    &perft::divide(&self.position, depth, self.castling_mode).to_string(),
+                );
+                self.log(
+                    Level::Debug,
+                    &format!("perft {} took {:?}", depth, start.elapsed()),
+                );
+            }
+
+            // Search fixed positions and report the node count and speed
+            EngineCommand::Bench => self.send_text(&bench::run(BENCH_DEPTH).to_string()),
+
+            // Show the position as the controller sees it
+            EngineCommand::D => {
+                self.send_text(&Display(&self.position, self.castling_mode).to_string())
+            }
+        }
+    }
+
+    /// Handles standard UCI commands
+    fn handle_uci(&mut self, message: UciMessage) -> bool {
         match message {
             // Uci handshake
             UciMessage::Uci => {
@@ -72,28 +200,30 @@ impl Controller {
                     name: None,
                     author: Some(env!("CARGO_PKG_AUTHORS").into()),
                 });
+                for option in OPTIONS {
+                    self.send_text(&option.to_string());
+                }
                 self.send(UciMessage::UciOk);
             }
             UciMessage::IsReady => self.send(UciMessage::ReadyOk),
 
+            // Configure the engine
+            UciMessage::SetOption { name, value } => self.set_option(&name, value.as_deref()),
+
             // Reset
             UciMessage::UciNewGame => {
-                self.position = Chess::default();
+                self.position = VariantPosition::new(self.variant);
             }
 
-            // Set a position
+            // Set a position, keeping the current one if the command is invalid
             UciMessage::Position { fen, moves, .. } => {
-                let mut position = if let Some(fen) = fen {
-                    fen.into_position(CastlingMode::Standard).unwrap()
-                } else {
-                    Chess::default()
-                };
-
-                for mv in moves {
-                    let m = mv.to_move(&position).unwrap();
-                    position = position.play(m).unwrap();
+                match setup_position(fen, &moves, self.variant, self.castling_mode) {
+                    Ok(position) => self.position = position,
+                    Err(err) => {
+                        self.log(Level::Warn, &format!("ERR: '{}'", err));
+                        self.send_text(&format!("info string Rejected position: {err}"));
+                    }
                 }
-                self.position = position;
             }
 
             // Search to fixed depth
@@ -103,22 +233,12 @@ impl Controller {
                         depth: Some(depth), ..
                     }),
                 ..
-            } => self
-                .cmd_tx
-                .send(SearchCommand::Start {
-                    position: self.position.clone(),
-                    control: SearchControl::ToDepth(depth),
-                })
-                .unwrap(),
+            } => self.start_search(SearchControl::ToDepth(depth)),
 
             // Any other search command will search for a fixed amount of time
-            UciMessage::Go { .. } => self
-                .cmd_tx
-                .send(SearchCommand::Start {
-                    position: self.position.clone(),
-                    control: SearchControl::TimeLimit(SEARCH_TIME_MS),
-                })
-                .unwrap(),
+            UciMessage::Go { .. } => self.start_search(SearchControl::TimeLimit(
+                SEARCH_TIME_MS.saturating_sub(self.move_overhead_ms),
+            )),
 
             // Stop current search
             UciMessage::Stop => self.cmd_tx.send(SearchCommand::Stop).unwrap(),
@@ -131,53 +251,329 @@ impl Controller {
         false
     }
 
+    /// Plays a move from the opening book if there is one, and otherwise searches
+    fn start_search(&mut self, control: SearchControl) {
+        if let Some(mv) = self.book_move() {
+            let mv = UciMove::from_move(mv, self.castling_mode);
+            self.log(Level::Info, &format!("Book move {mv}"));
+            // User interfaces like En Croissant need an info line before the best move
+            self.send(UciMessage::Info(UciInfo {
+                depth: Some(0),
+                pv: vec![mv],
+                ..Default::default()
+            }));
+            self.send(UciMessage::BestMove {
+                best_move: mv,
+                ponder: None,
+            });
+            return;
+        }
+
+        self.search_material = wdl::material(&self.position);
+        self.cmd_tx
+            .send(SearchCommand::Start {
+                position: self.position.clone(),
+                control,
+            })
+            .unwrap();
+    }
+
+    /// A move from the book for the current position, if the book is in use
+    fn book_move(&self) -> Option<Move> {
+        let book = self.book.as_ref().filter(|_| self.own_book)?;
+        // Polyglot books are for standard chess only
+        let VariantPosition::Chess(position) = &self.position else {
+            return None;
+        };
+        let ply = (position.fullmoves().get() - 1) * 2 + position.turn().fold_wb(0, 1);
+        if ply >= self.book_depth {
+            return None;
+        }
+        book.choose(position, self.book_selection)
+    }
+
+    /// Applies an option sent by the user interface
+    fn set_option(&mut self, name: &str, value: Option<&str>) {
+        let Some(option) = options::find(name) else {
+            self.log(Level::Warn, &format!("ERR: 'unknown option: {}'", name));
+            self.send_text(&format!("info string Unknown option '{name}'"));
+            return;
+        };
+        match option.parse(value) {
+            Ok(value) => self.apply_option(option, value),
+            Err(err) => {
+                self.log(Level::Warn, &format!("ERR: '{}'", err));
+                self.send_text(&format!("info string {err}"));
+            }
+        }
+    }
+
+    fn apply_option(&mut self, option: &UciOption, value: OptionValue) {
+        match (option.name, value) {
+            (options::HASH, OptionValue::Spin(mb)) => {
+                self.search_options.hash_mb = mb as usize;
+                self.update_search_options();
+            }
+
+            (options::MULTI_PV, OptionValue::Spin(lines)) => {
+                self.search_options.multi_pv = lines as u8;
+                self.update_search_options();
+            }
+
+            // Time kept in reserve for communication with the user interface
+            (options::MOVE_OVERHEAD, OptionValue::Spin(ms)) => self.move_overhead_ms = ms as u64,
+
+            (options::CLEAR_HASH, _) => self.cmd_tx.send(SearchCommand::ClearHash).unwrap(),
+
+            // Logging, an empty file name disables it
+            (options::DEBUG_LOG_FILE, OptionValue::String(path)) => {
+                self.logger.set_path(&path);
+                crash::set_log_file(&path);
+            }
+            (options::DEBUG_LOG_LEVEL, OptionValue::Combo(name)) => {
+                self.logger
+                    .set_level(Level::from_name(name).unwrap_or(Level::Info));
+            }
+            (options::DEBUG_LOG_FORMAT, OptionValue::Combo(name)) => {
+                let format = match name {
+                    "JSON" => Format::Json,
+                    _ => Format::Text,
+                };
+                self.logger.set_format(format);
+            }
+
+            // Load a network, an empty value restores the embedded one
+            (options::EVAL_FILE, OptionValue::String(path)) => {
+                if path.is_empty() {
+                    self.network = Network::embedded().map(Arc::new);
+                } else {
+                    match Network::load(&path) {
+                        Ok(network) => self.network = Some(Arc::new(network)),
+                        Err(err) => {
+                            self.send_text(&format!("info string Failed to load '{path}': {err}"));
+                            return;
+                        }
+                    }
+                }
+                self.update_evaluation();
+            }
+
+            // Switch between the network and the handcrafted evaluation
+            (options::USE_NNUE, OptionValue::Check(use_nnue)) => {
+                self.use_nnue = use_nnue;
+                self.update_evaluation();
+            }
+
+            // Choose how to search
+            (options::SEARCH_ALGORITHM, OptionValue::Combo(name)) => {
+                let algorithm: Box<dyn SearchAlgorithm> = match name {
+                    "MCTS" => Box::new(Mcts),
+                    _ => Box::new(Negamax),
+                };
+                self.cmd_tx
+                    .send(SearchCommand::SetAlgorithm(algorithm))
+                    .unwrap();
+            }
+
+            (options::CHESS960, OptionValue::Check(chess960)) => {
+                self.castling_mode = CastlingMode::from_chess960(chess960)
+            }
+
+            // Switch rules, starting over from the start position of the variant
+            (options::VARIANT, OptionValue::Combo(name)) => {
+                self.variant = Variant::from_uci(name).unwrap_or_default();
+                self.position = VariantPosition::new(self.variant);
+                self.update_evaluation();
+            }
+
+            (options::SHOW_WDL, OptionValue::Check(show_wdl)) => self.show_wdl = show_wdl,
+
+            // Playing strength
+            (options::SKILL_LEVEL, OptionValue::Spin(level)) => {
+                self.skill_level = level as u8;
+                self.update_skill();
+            }
+            (options::LIMIT_STRENGTH, OptionValue::Check(limit_strength)) => {
+                self.limit_strength = limit_strength;
+                self.update_skill();
+            }
+            (options::ELO, OptionValue::Spin(elo)) => {
+                self.elo = elo as u16;
+                self.update_skill();
+            }
+
+            // Opening book, an empty file name unloads it
+            (options::OWN_BOOK, OptionValue::Check(own_book)) => self.own_book = own_book,
+            (options::BOOK_FILE, OptionValue::String(path)) => {
+                self.book = None;
+                if path.is_empty() {
+                    return;
+                }
+                match Book::open(&path) {
+                    Ok(book) => {
+                        self.log(
+                            Level::Info,
+                            &format!("Loaded book '{path}' with {} entries", book.len()),
+                        );
+                        self.book = Some(book);
+                    }
+                    Err(err) => {
+                        self.log(Level::Warn, &format!("ERR: 'failed to load book: {}'", err));
+                        self.send_text(&format!("info string Failed to load '{path}': {err}"));
+                    }
+                }
+            }
+            // Endgame tablebases, an empty path stops using them
+            (options::SYZYGY_PATH, OptionValue::String(path)) => {
+                let tablebases = if path.is_empty() {
+                    None
+                } else {
+                    match Tablebases::open(&path) {
+                        Ok(tablebases) => {
+                            self.log(
+                                Level::Info,
+                                &format!(
+                                    "Loaded tablebases for up to {} pieces",
+                                    tablebases.max_pieces()
+                                ),
+                            );
+                            Some(tablebases)
+                        }
+                        Err(err) => {
+                            self.log(
+                                Level::Warn,
+                                &format!("ERR: 'failed to load tablebases: {}'", err),
+                            );
+                            self.send_text(&format!("info string Failed to load '{path}': {err}"));
+                            None
+                        }
+                    }
+                };
+                self.cmd_tx
+                    .send(SearchCommand::SetTablebases(tablebases))
+                    .unwrap();
+            }
+
+            (options::BOOK_DEPTH, OptionValue::Spin(plies)) => self.book_depth = plies as u32,
+            (options::BOOK_SELECTION, OptionValue::Combo(name)) => {
+                self.book_selection = match name {
+                    "Best" => Selection::Best,
+                    _ => Selection::Weighted,
+                };
+            }
+
+            // Threads only allows its default
+            _ => (),
+        }
+    }
+
+    /// Tells the searcher about changed search settings
+    fn update_search_options(&self) {
+        self.cmd_tx
+            .send(SearchCommand::SetOptions(self.search_options.clone()))
+            .unwrap();
+    }
+
+    /// Tells the searcher how well to play
+    fn update_skill(&mut self) {
+        self.search_options.skill = if self.limit_strength {
+            Skill::from_elo(self.elo)
+        } else {
+            Skill::new(self.skill_level)
+        };
+        self.update_search_options();
+    }
+
+    /// Tells the searcher which evaluation to use
+    fn update_evaluation(&self) {
+        // The network is trained on, and only updated correctly for, standard chess
+        let evaluator: Box<dyn Evaluator> = match &self.network {
+            Some(network) if self.use_nnue && self.variant == Variant::Chess => {
+                Box::new(NnueState::new(network.clone()))
+            }
+            _ => Box::new(Handcrafted::default()),
+        };
+        self.cmd_tx
+            .send(SearchCommand::SetEvaluator(evaluator))
+            .unwrap();
+    }
+
     fn handle_info(&mut self, message: SearchInfo) {
         match message {
-            // Emit best move to user interface
+            // Emit best move to user interface, the null move if there is none
             SearchInfo::BestMove(mv) => self.send(UciMessage::BestMove {
-                best_move: UciMove::from_move(mv, CastlingMode::Standard),
+                best_move: mv.map_or(UciMove::Null, |mv| {
+                    UciMove::from_move(mv, self.castling_mode)
+                }),
                 ponder: None,
             }),
 
             // Emit info to user interface
             SearchInfo::Info {
                 depth,
+                seldepth,
+                multipv,
                 pv,
                 score,
                 nodes,
+                time,
+                hashfull,
+                tbhits,
             } => {
+                let time_ms = time.as_millis() as u64;
                 let info_msg = UciMessage::Info(UciInfo {
                     depth: Some(depth),
+                    seldepth: Some(seldepth),
+                    time: Some(time_ms),
                     score: Some(UciInfoScore {
                         cp: Some(score),
                         ..Default::default()
                     }),
                     pv: pv
                         .into_iter()
-                        .map(|mv| UciMove::from_move(mv, CastlingMode::Standard))
+                        .map(|mv| UciMove::from_move(mv, self.castling_mode))
                         .collect(),
                     nodes: Some(nodes),
+                    nps: Some(nodes * 1000 / time_ms.max(1)),
+                    hashfull,
+                    tbhits: (tbhits > 0).then_some(tbhits),
+                    multipv: Some(multipv as u16),
 
                     ..Default::default()
                 });
 
-                self.send(info_msg);
+                if !self.show_wdl {
+                    self.send(info_msg);
+                    return;
+                }
+                // `UciInfo` has no field for it, so it is added to the rendered line before the pv
+                let wdl = WDL_MODEL.wdl(score, self.search_material);
+                let wdl = format!(" wdl {} {} {}", wdl.win, wdl.draw, wdl.loss);
+                let mut line = info_msg.to_string();
+                let at = line.find(" pv ").unwrap_or(line.len());
+                line.insert_str(at, &wdl);
+                self.write_line(&line);
+            }
+
+            // Emit the root move being searched
+            SearchInfo::CurrMove { depth, mv, number } => {
+                self.send(UciMessage::Info(UciInfo {
+                    depth: Some(depth),
+                    currmove: Some(UciMove::from_move(mv, self.castling_mode)),
+                    currmovenumber: Some(number),
+                    ..Default::default()
+                }));
             }
         }
     }
 
-    fn log(&self, line: &str) {
-        let mut log_file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(self.log_file)
-            .unwrap();
-        writeln!(&mut log_file, "{}", line).unwrap()
+    fn log(&self, level: Level, line: &str) {
+        self.logger.log(level, line);
     }
 }
 
-impl Drop for Controller {
+impl<W: Write, L: Logger> Drop for Controller<W, L> {
     fn drop(&mut self) {
-        self.log("------ Engine closed ------");
+        self.log(Level::Info, "------ Engine closed ------");
     }
 }
@@ -0,0 +1,164 @@
+//! Crash reports, written when any thread of the engine panics.
+//!
+//! The front ends record the commands they receive and the position they are in, so a report
+//! shows what led to the crash. Reports go to the runtime log and to the crash file. The runner
+//! keeps its own [`Recent`] commands, for engines that die without writing a report.
+
+use chrono::Local;
+use std::backtrace::Backtrace;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::panic;
+use std::sync::Mutex;
+use std::thread;
+
+/// File crash reports are appended to
+pub const CRASH_FILE: &str = "crash.log";
+/// Number of received commands included in a report
+pub const RECENT_COMMANDS: usize = 20;
+
+/// What the engine was doing, for the report
+struct Context {
+    commands: VecDeque<String>,
+    fen: String,
+    log_file: String,
+}
+
+static CONTEXT: Mutex<Context> = Mutex::new(Context {
+    commands: VecDeque::new(),
+    fen: String::new(),
+    log_file: String::new(),
+});
+
+fn with_context(f: impl FnOnce(&mut Context)) {
+    f(&mut CONTEXT.lock().unwrap_or_else(|err| err.into_inner()));
+}
+
+/// Write a report for every panic, after the usual message on stderr
+pub fn install(log_file: &str) {
+    set_log_file(log_file);
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        let message = info
+            .payload()
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| info.payload().downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("unknown panic");
+        let location = info
+            .location()
+            .map_or("unknown location".to_string(), |location| {
+                location.to_string()
+            });
+        let report = report(message, &location);
+        append(CRASH_FILE, &report);
+        // The log is written directly, its writer thread may be the one that panicked
+        let log_file = CONTEXT
+            .try_lock()
+            .map(|context| context.log_file.clone())
+            .unwrap_or_default();
+        append(&log_file, &report);
+    }));
+}
+
+/// Remember a command received from the user interface
+pub fn record_command(line: &str) {
+    with_context(|context| {
+        if context.commands.len() == RECENT_COMMANDS {
+            context.commands.pop_front();
+        }
+        context.commands.push_back(line.to_string());
+    });
+}
+
+/// Remember the current position
+pub fn record_position(fen: String) {
+    with_context(|context| context.fen = fen);
+}
+
+/// Follow the runtime log to another file, an empty path leaves crashes out of the log
+pub fn set_log_file(path: &str) {
+    with_context(|context| context.log_file = path.to_string());
+}
+
+/// Report of a panic on the current thread, with the recorded position and commands
+pub fn report(message: &str, location: &str) -> String {
+    let thread = thread::current();
+
+    let mut report = String::new();
+    let _ = writeln!(
+        report,
+        "------ Crash at {} ------",
+        Local::now().format("%Y-%m-%d %H:%M:%S%.3f")
+    );
+    let _ = writeln!(
+        report,
+        "Thread '{}' panicked at {location}: {message}",
+        thread.name().unwrap_or("unnamed")
+    );
+
+    // The panicking thread may hold the context, it is left out rather than waited for
+    match CONTEXT.try_lock() {
+        Ok(context) => {
+            let _ = writeln!(report, "Position: {}", context.fen);
+            let _ = writeln!(report, "Last commands:");
+            for command in &context.commands {
+                let _ = writeln!(report, "  {command}");
+            }
+        }
+        Err(_) => {
+            let _ = writeln!(report, "Position and last commands unavailable");
+        }
+    }
+
+    let _ = write!(report, "Backtrace:\n{}", Backtrace::force_capture());
+    report
+}
+
+/// Append to a file, ignoring errors as there is nothing left to report them to
+fn append(path: &str, text: &str) {
+    if path.is_empty() {
+        return;
+    }
+    if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(path) {
+        let _ = writeln!(file, "{text}");
+    }
+}
+
+/// Lines piped to the engine, kept as they were sent
+#[derive(Debug, Default)]
+pub struct Recent {
+    commands: VecDeque<String>,
+    /// Start of a line whose end is still to be read
+    partial: Vec<u8>,
+}
+
+impl Recent {
+    /// Keep the complete lines of a chunk of input, returns whether one of them is `quit`
+    pub fn remember(&mut self, chunk: &[u8]) -> bool {
+        self.partial.extend_from_slice(chunk);
+        let mut quit = false;
+        while let Some(end) = self.partial.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.partial.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            quit |= line.to_lowercase() == "quit";
+            if self.commands.len() == RECENT_COMMANDS {
+                self.commands.pop_front();
+            }
+            self.commands.push_back(line.to_string());
+        }
+        quit
+    }
+
+    /// The last complete lines, oldest first
+    pub fn commands(&self) -> impl Iterator<Item = &str> {
+        self.commands.iter().map(String::as_str)
+    }
+}
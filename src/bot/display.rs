@@ -0,0 +1,102 @@
+//! Human readable description of a position, printed by the `d` command.
+
+use shakmaty::fen::Fen;
+use shakmaty::uci::UciMove;
+use shakmaty::zobrist::Zobrist64;
+use shakmaty::{
+    CastlingMode, CastlingSide, Color, EnPassantMode, File, Position, Rank, Role, Square,
+};
+use std::fmt;
+
+/// Board diagram followed by the state of the position, with castling moves written for the mode
+pub struct Display<'a, P>(pub &'a P, pub CastlingMode);
+
+impl<P: Position> fmt::Display for Display<'_, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let position = self.0;
+        let separator = " +---+---+---+---+---+---+---+---+";
+
+        // Board, seen from white's side
+        writeln!(f, "{separator}")?;
+        for rank in Rank::ALL.into_iter().rev() {
+            for file in File::ALL {
+                let piece = position.board().piece_at(Square::from_coords(file, rank));
+                write!(f, " | {}", piece.map_or(' ', |piece| piece.char()))?;
+            }
+            writeln!(f, " | {}", rank.char())?;
+            writeln!(f, "{separator}")?;
+        }
+        writeln!(f, "   a   b   c   d   e   f   g   h")?;
+        writeln!(f)?;
+
+        let turn = match position.turn() {
+            Color::White => "white",
+            Color::Black => "black",
+        };
+        let castles = position.castles();
+        let mut castling: String = [
+            (Color::White, CastlingSide::KingSide, 'K'),
+            (Color::White, CastlingSide::QueenSide, 'Q'),
+            (Color::Black, CastlingSide::KingSide, 'k'),
+            (Color::Black, CastlingSide::QueenSide, 'q'),
+        ]
+        .into_iter()
+        .filter(|&(color, side, _)| castles.has(color, side))
+        .map(|(_, _, c)| c)
+        .collect();
+        if castling.is_empty() {
+            castling.push('-');
+        }
+        let en_passant = position
+            .ep_square(EnPassantMode::Legal)
+            .map_or("-".to_string(), |sq| sq.to_string());
+        let key = position.zobrist_hash::<Zobrist64>(EnPassantMode::Legal).0;
+        let checkers: Vec<String> = position
+            .checkers()
+            .into_iter()
+            .map(|sq| sq.to_string())
+            .collect();
+        let moves: Vec<String> = position
+            .legal_moves()
+            .into_iter()
+            .map(|mv| UciMove::from_move(mv, self.1).to_string())
+            .collect();
+
+        writeln!(
+            f,
+            "Fen: {}",
+            Fen::from_position(position, EnPassantMode::Legal)
+        )?;
+        writeln!(f, "Side to move: {turn}")?;
+        writeln!(f, "Castling: {castling}")?;
+        writeln!(f, "En passant: {en_passant}")?;
+        writeln!(f, "Key: {key:016X}")?;
+        writeln!(f, "Checkers: {}", checkers.join(" "))?;
+
+        // Pieces in hand, in Crazyhouse
+        if let Some(pockets) = position.pockets() {
+            for color in Color::ALL {
+                let pocket: String = Role::ALL
+                    .into_iter()
+                    .flat_map(|role| {
+                        let count = *pockets.get(color).get(role) as usize;
+                        std::iter::repeat_n(role.of(color).char(), count)
+                    })
+                    .collect();
+                writeln!(f, "Pocket ({}): {pocket}", color)?;
+            }
+        }
+
+        // Checks left to give, in Three-check
+        if let Some(checks) = position.remaining_checks() {
+            writeln!(
+                f,
+                "Remaining checks: white {}, black {}",
+                u32::from(checks.white),
+                u32::from(checks.black)
+            )?;
+        }
+
+        write!(f, "Legal moves ({}): {}", moves.len(), moves.join(" "))
+    }
+}
@@ -1,33 +1,118 @@
 use crossbeam_channel::Sender;
 use shakmaty_uci::UciMessage;
+use std::fmt;
 use std::io::{self, BufRead};
 
+/// Commands read from stdin
+pub enum Input {
+    /// Standard UCI command
+    Uci(UciMessage),
+    /// Engine specific command, not part of the UCI protocol
+    Engine(EngineCommand),
+    /// Line that could not be understood
+    Unknown(String),
+}
+
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Input::Uci(msg) => write!(f, "{msg}"),
+            Input::Engine(cmd) => write!(f, "{cmd}"),
+            Input::Unknown(line) => write!(f, "{line}"),
+        }
+    }
+}
+
+/// Commands for debugging and testing the engine from a terminal
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EngineCommand {
+    /// Display the current position
+    D,
+    /// Print the evaluation breakdown of the current position
+    Eval,
+    /// Count the leaf nodes of the move tree to a depth, as `perft N` or `go perft N`
+    Perft(u8),
+    /// Search a fixed set of positions and report the speed
+    Bench,
+}
+
+impl EngineCommand {
+    /// Parse an engine specific command, `None` if the line is not one
+    pub fn parse(line: &str) -> Option<EngineCommand> {
+        let mut tokens = line.split_whitespace();
+        let command = match (tokens.next()?, tokens.next()) {
+            ("d", None) => EngineCommand::D,
+            ("eval", None) => EngineCommand::Eval,
+            ("perft", Some(depth)) => EngineCommand::Perft(depth.parse().ok()?),
+            ("go", Some("perft")) => EngineCommand::Perft(tokens.next()?.parse().ok()?),
+            ("bench", None) => EngineCommand::Bench,
+            _ => return None,
+        };
+        tokens.next().is_none().then_some(command)
+    }
+}
+
+impl fmt::Display for EngineCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineCommand::D => write!(f, "d"),
+            EngineCommand::Eval => write!(f, "eval"),
+            EngineCommand::Perft(depth) => write!(f, "perft {depth}"),
+            EngineCommand::Bench => write!(f, "bench"),
+        }
+    }
+}
+
 /// Listens for UCI commands on stdin and forwards them to the input channel.
 pub struct InputListener {
-    input_tx: Sender<UciMessage>,
+    input_tx: Sender<Input>,
 }
 
 impl InputListener {
-    pub fn new(input_tx: Sender<UciMessage>) -> Self {
+    pub fn new(input_tx: Sender<Input>) -> Self {
         Self { input_tx }
     }
 
     pub fn run(self) {
-        let stdin = io::stdin();
+        let mut stdin = io::stdin().lock();
 
         // Listen while stdin is open
-        for line_result in stdin.lock().lines() {
-            let line = if let Ok(l) = line_result { l } else { continue };
-
-            let trimmed = line.trim();
-            if trimmed.is_empty() {
-                continue;
-            }
-
-            // Try to parse as UciMessage. Ignore invalid input.
-            if let Ok(msg) = trimmed.parse::<UciMessage>() {
-                self.input_tx.send(msg).unwrap();
-            }
+        while let Some(line) = read_line(&mut stdin) {
+            self.forward(&line);
         }
     }
+
+    /// Parse a line and send it to the controller, e.g. one read before the listener started
+    pub fn forward(&self, line: &str) {
+        let trimmed = line.trim();
+        if !trimmed.is_empty() {
+            self.input_tx.send(parse_input(trimmed)).unwrap();
+        }
+    }
+}
+
+/// Read the next line, `None` once the input is closed.
+///
+/// Invalid UTF-8 is kept, so the line can still be reported.
+pub fn read_line(reader: &mut impl BufRead) -> Option<String> {
+    let mut buffer = Vec::new();
+    loop {
+        match reader.read_until(b'\n', &mut buffer) {
+            Ok(0) => return None,
+            Ok(_) => return Some(String::from_utf8_lossy(&buffer).into_owned()),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(_) => return None,
+        }
+    }
+}
+
+/// Classify a line of input
+pub fn parse_input(line: &str) -> Input {
+    if let Some(cmd) = EngineCommand::parse(line) {
+        return Input::Engine(cmd);
+    }
+    match line.parse::<UciMessage>() {
+        Ok(msg) => Input::Uci(msg),
+        Err(_) => Input::Unknown(line.to_string()),
+    }
 }
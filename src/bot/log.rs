@@ -0,0 +1,252 @@
+//! Runtime log shared by the UCI and xboard front ends.
+//!
+//! Lines are written by a background thread, so logging never waits for the disk, and a log file
+//! that can't be written only disables logging.
+
+use chrono::{DateTime, Local};
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use std::fmt::Write as _;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::thread::{self, JoinHandle};
+
+// Parameters
+/// Size at which the log file is moved to `<path>.1` and a new one is started
+const MAX_LOG_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Importance of a log line, lines less important than the logger's level are dropped
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+impl Level {
+    pub const ALL: [Level; 4] = [Level::Error, Level::Warn, Level::Info, Level::Debug];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Level::Error => "Error",
+            Level::Warn => "Warn",
+            Level::Info => "Info",
+            Level::Debug => "Debug",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Level> {
+        Level::ALL
+            .into_iter()
+            .find(|level| level.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// How log lines are written
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// Timestamp, level and message separated by spaces
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+/// Destination of the runtime log
+pub trait Logger: Send {
+    fn log(&self, level: Level, line: &str);
+
+    /// Log to another file, an empty path disables logging
+    fn set_path(&mut self, path: &str);
+
+    fn set_level(&mut self, level: Level);
+
+    fn set_format(&mut self, format: Format);
+}
+
+/// Requests to the writer thread
+enum Message {
+    Line {
+        time: DateTime<Local>,
+        level: Level,
+        line: String,
+    },
+    SetPath(String),
+    SetFormat(Format),
+}
+
+/// Appends lines to a file from a background thread, rotating the file once it gets large
+pub struct FileLogger {
+    /// Closed when the logger is dropped, which ends the writer thread
+    message_tx: Option<Sender<Message>>,
+    writer: Option<JoinHandle<()>>,
+    level: Level,
+}
+
+impl FileLogger {
+    /// Log to `path` at level `Info` as text, logging is disabled if the path is empty
+    pub fn new(path: &str) -> Self {
+        let (message_tx, message_rx) = unbounded();
+        let mut writer = Writer::new();
+        writer.open(path);
+        let writer = thread::spawn(move || writer.run(message_rx));
+
+        FileLogger {
+            message_tx: Some(message_tx),
+            writer: Some(writer),
+            level: Level::Info,
+        }
+    }
+
+    fn send(&self, message: Message) {
+        if let Some(message_tx) = &self.message_tx {
+            // The writer only stops once the logger is dropped
+            let _ = message_tx.send(message);
+        }
+    }
+}
+
+impl Logger for FileLogger {
+    fn log(&self, level: Level, line: &str) {
+        if level <= self.level {
+            self.send(Message::Line {
+                time: Local::now(),
+                level,
+                line: line.to_string(),
+            });
+        }
+    }
+
+    fn set_path(&mut self, path: &str) {
+        self.send(Message::SetPath(path.to_string()));
+    }
+
+    fn set_level(&mut self, level: Level) {
+        self.level = level;
+    }
+
+    fn set_format(&mut self, format: Format) {
+        self.send(Message::SetFormat(format));
+    }
+}
+
+/// Waits for the lines logged so far to be written
+impl Drop for FileLogger {
+    fn drop(&mut self) {
+        self.message_tx.take();
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+/// State of the writer thread
+struct Writer {
+    path: String,
+    file: Option<BufWriter<File>>,
+    /// Size of the current file
+    size: u64,
+    format: Format,
+}
+
+impl Writer {
+    fn new() -> Self {
+        Writer {
+            path: String::new(),
+            file: None,
+            size: 0,
+            format: Format::Text,
+        }
+    }
+
+    fn run(mut self, message_rx: Receiver<Message>) {
+        for message in message_rx.iter() {
+            match message {
+                Message::Line { time, level, line } => self.write(time, level, &line),
+                Message::SetPath(path) => self.open(&path),
+                Message::SetFormat(format) => self.format = format,
+            }
+
+            // Write out once there is nothing more to do, so no lines are lost if the engine dies
+            if message_rx.is_empty() {
+                self.flush();
+            }
+        }
+        self.flush();
+    }
+
+    /// Continue logging to `path`, without logging if it can't be opened
+    fn open(&mut self, path: &str) {
+        self.flush();
+        self.path = path.to_string();
+        self.file = None;
+        self.size = 0;
+        if path.is_empty() {
+            return;
+        }
+        if let Ok(file) = OpenOptions::new().create(true).append(true).open(path) {
+            self.size = file.metadata().map_or(0, |metadata| metadata.len());
+            self.file = Some(BufWriter::new(file));
+        }
+    }
+
+    fn write(&mut self, time: DateTime<Local>, level: Level, line: &str) {
+        let Some(file) = &mut self.file else {
+            return;
+        };
+
+        let time = time.format("%Y-%m-%d %H:%M:%S%.3f");
+        let text = match self.format {
+            Format::Text => format!("{time} {:<5} {line}\n", level.name()),
+            Format::Json => format!(
+                "{{\"time\":\"{time}\",\"level\":\"{}\",\"message\":\"{}\"}}\n",
+                level.name().to_lowercase(),
+                escape_json(line)
+            ),
+        };
+        if file.write_all(text.as_bytes()).is_err() {
+            self.file = None;
+            return;
+        }
+
+        self.size += text.len() as u64;
+        if self.size >= MAX_LOG_BYTES {
+            self.rotate();
+        }
+    }
+
+    /// Keep the full file as `<path>.1`, replacing the one kept before, and start a new one
+    fn rotate(&mut self) {
+        self.flush();
+        self.file = None;
+        let path = self.path.clone();
+        let _ = fs::rename(&path, format!("{path}.1"));
+        self.open(&path);
+    }
+
+    fn flush(&mut self) {
+        if let Some(file) = &mut self.file {
+            if file.flush().is_err() {
+                self.file = None;
+            }
+        }
+    }
+}
+
+/// Escape a string for use inside a JSON string literal
+fn escape_json(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
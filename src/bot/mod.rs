@@ -1,2 +1,10 @@
+pub mod book;
 pub mod controller;
+pub mod crash;
+pub mod display;
 pub mod input;
+pub mod log;
+pub mod options;
+pub mod pgn;
+pub mod position;
+pub mod xboard;
@@ -0,0 +1,289 @@
+//! Options the engine advertises to the user interface and accepts through `setoption`.
+
+use crate::search::skill::{MAX_ELO, MAX_LEVEL, MIN_ELO};
+use std::fmt;
+
+// Option names
+pub const HASH: &str = "Hash";
+pub const THREADS: &str = "Threads";
+pub const MULTI_PV: &str = "MultiPV";
+pub const MOVE_OVERHEAD: &str = "Move Overhead";
+pub const CLEAR_HASH: &str = "Clear Hash";
+pub const DEBUG_LOG_FILE: &str = "Debug Log File";
+pub const DEBUG_LOG_LEVEL: &str = "Debug Log Level";
+pub const DEBUG_LOG_FORMAT: &str = "Debug Log Format";
+pub const EVAL_FILE: &str = "EvalFile";
+pub const USE_NNUE: &str = "Use NNUE";
+pub const SEARCH_ALGORITHM: &str = "SearchAlgorithm";
+pub const CHESS960: &str = "UCI_Chess960";
+pub const VARIANT: &str = "UCI_Variant";
+pub const SHOW_WDL: &str = "UCI_ShowWDL";
+pub const SKILL_LEVEL: &str = "Skill Level";
+pub const LIMIT_STRENGTH: &str = "UCI_LimitStrength";
+pub const ELO: &str = "UCI_Elo";
+pub const OWN_BOOK: &str = "OwnBook";
+pub const BOOK_FILE: &str = "BookFile";
+pub const BOOK_DEPTH: &str = "BookDepth";
+pub const BOOK_SELECTION: &str = "BookSelection";
+pub const SYZYGY_PATH: &str = "SyzygyPath";
+
+/// All options, in the order they are advertised
+pub const OPTIONS: &[UciOption] = &[
+    UciOption {
+        name: HASH,
+        kind: OptionType::Spin {
+            default: 16,
+            min: 1,
+            max: 1024,
+        },
+    },
+    // Fixed at one thread, the search is single-threaded
+    UciOption {
+        name: THREADS,
+        kind: OptionType::Spin {
+            default: 1,
+            min: 1,
+            max: 1,
+        },
+    },
+    UciOption {
+        name: MULTI_PV,
+        kind: OptionType::Spin {
+            default: 1,
+            min: 1,
+            max: 64,
+        },
+    },
+    UciOption {
+        name: MOVE_OVERHEAD,
+        kind: OptionType::Spin {
+            default: 10,
+            min: 0,
+            max: 5000,
+        },
+    },
+    UciOption {
+        name: CLEAR_HASH,
+        kind: OptionType::Button,
+    },
+    UciOption {
+        name: DEBUG_LOG_FILE,
+        kind: OptionType::String {
+            default: "engine.log",
+        },
+    },
+    UciOption {
+        name: DEBUG_LOG_LEVEL,
+        kind: OptionType::Combo {
+            default: "Info",
+            vars: &["Error", "Warn", "Info", "Debug"],
+        },
+    },
+    UciOption {
+        name: DEBUG_LOG_FORMAT,
+        kind: OptionType::Combo {
+            default: "Text",
+            vars: &["Text", "JSON"],
+        },
+    },
+    UciOption {
+        name: EVAL_FILE,
+        kind: OptionType::String { default: "" },
+    },
+    UciOption {
+        name: USE_NNUE,
+        kind: OptionType::Check { default: true },
+    },
+    UciOption {
+        name: SEARCH_ALGORITHM,
+        kind: OptionType::Combo {
+            default: "Negamax",
+            vars: &["Negamax", "MCTS"],
+        },
+    },
+    UciOption {
+        name: CHESS960,
+        kind: OptionType::Check { default: false },
+    },
+    // Names as used by shakmaty's `Variant::from_uci`
+    UciOption {
+        name: VARIANT,
+        kind: OptionType::Combo {
+            default: "chess",
+            vars: &[
+                "chess",
+                "atomic",
+                "antichess",
+                "kingofthehill",
+                "3check",
+                "crazyhouse",
+                "racingkings",
+                "horde",
+            ],
+        },
+    },
+    UciOption {
+        name: SHOW_WDL,
+        kind: OptionType::Check { default: false },
+    },
+    UciOption {
+        name: SKILL_LEVEL,
+        kind: OptionType::Spin {
+            default: MAX_LEVEL as i64,
+            min: 0,
+            max: MAX_LEVEL as i64,
+        },
+    },
+    // `UCI_Elo` takes the place of `Skill Level` while strength is limited. The ratings are
+    // approximate, see the calibration in `search::skill`
+    UciOption {
+        name: LIMIT_STRENGTH,
+        kind: OptionType::Check { default: false },
+    },
+    UciOption {
+        name: ELO,
+        kind: OptionType::Spin {
+            default: MIN_ELO as i64,
+            min: MIN_ELO as i64,
+            max: MAX_ELO as i64,
+        },
+    },
+    UciOption {
+        name: OWN_BOOK,
+        kind: OptionType::Check { default: false },
+    },
+    UciOption {
+        name: BOOK_FILE,
+        kind: OptionType::String { default: "" },
+    },
+    // Plies from the start of the game the book is used for
+    UciOption {
+        name: BOOK_DEPTH,
+        kind: OptionType::Spin {
+            default: 20,
+            min: 0,
+            max: 200,
+        },
+    },
+    UciOption {
+        name: BOOK_SELECTION,
+        kind: OptionType::Combo {
+            default: "Weighted",
+            vars: &["Weighted", "Best"],
+        },
+    },
+    // Directories of Syzygy tables, separated like in the `PATH` variable
+    UciOption {
+        name: SYZYGY_PATH,
+        kind: OptionType::String { default: "" },
+    },
+];
+
+/// Look up an option by name, ignoring case as the UCI protocol requires
+pub fn find(name: &str) -> Option<&'static UciOption> {
+    OPTIONS
+        .iter()
+        .find(|option| option.name.eq_ignore_ascii_case(name.trim()))
+}
+
+/// Kinds of options defined by the UCI protocol
+#[derive(Clone, Copy, Debug)]
+pub enum OptionType {
+    Spin {
+        default: i64,
+        min: i64,
+        max: i64,
+    },
+    Check {
+        default: bool,
+    },
+    Combo {
+        default: &'static str,
+        vars: &'static [&'static str],
+    },
+    String {
+        default: &'static str,
+    },
+    Button,
+}
+
+/// A value given to an option
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OptionValue {
+    Spin(i64),
+    Check(bool),
+    Combo(&'static str),
+    String(String),
+    Button,
+}
+
+/// An option with its type and default value
+#[derive(Clone, Copy, Debug)]
+pub struct UciOption {
+    pub name: &'static str,
+    pub kind: OptionType,
+}
+
+impl UciOption {
+    /// Check a value sent by the user interface against the type of the option. Numbers outside
+    /// the range of a spin option are clamped to it.
+    pub fn parse(&self, value: Option<&str>) -> Result<OptionValue, String> {
+        let value = value.map(str::trim).unwrap_or_default();
+        match self.kind {
+            OptionType::Spin { min, max, .. } => match value.parse::<i64>() {
+                Ok(n) => Ok(OptionValue::Spin(n.clamp(min, max))),
+                Err(_) => Err(format!(
+                    "{} must be a number from {min} to {max}, got '{value}'",
+                    self.name
+                )),
+            },
+            OptionType::Check { .. } => match value.to_lowercase().as_str() {
+                "true" => Ok(OptionValue::Check(true)),
+                "false" => Ok(OptionValue::Check(false)),
+                _ => Err(format!(
+                    "{} must be true or false, got '{value}'",
+                    self.name
+                )),
+            },
+            OptionType::Combo { vars, .. } => vars
+                .iter()
+                .find(|var| var.eq_ignore_ascii_case(value))
+                .map(|var| OptionValue::Combo(var))
+                .ok_or_else(|| {
+                    format!(
+                        "{} must be one of {}, got '{value}'",
+                        self.name,
+                        vars.join(", ")
+                    )
+                }),
+            OptionType::String { .. } => match value {
+                "<empty>" => Ok(OptionValue::String(String::new())),
+                _ => Ok(OptionValue::String(value.to_string())),
+            },
+            OptionType::Button => Ok(OptionValue::Button),
+        }
+    }
+}
+
+/// Formats the option as advertised during the UCI handshake
+impl fmt::Display for UciOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "option name {} type ", self.name)?;
+        match self.kind {
+            OptionType::Spin { default, min, max } => {
+                write!(f, "spin default {default} min {min} max {max}")
+            }
+            OptionType::Check { default } => write!(f, "check default {default}"),
+            OptionType::Combo { default, vars } => {
+                write!(f, "combo default {default}")?;
+                for var in vars {
+                    write!(f, " var {var}")?;
+                }
+                Ok(())
+            }
+            OptionType::String { default: "" } => write!(f, "string default <empty>"),
+            OptionType::String { default } => write!(f, "string default {default}"),
+            OptionType::Button => write!(f, "button"),
+        }
+    }
+}
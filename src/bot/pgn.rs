@@ -0,0 +1,179 @@
+//! Finished games read from PGN files, for the tools that learn from them.
+//!
+//! Only standard chess games with a result are read. Games in other variants, from start
+//! positions that can't be read, with illegal moves or unfinished are skipped.
+
+use pgn_reader::{KnownOutcome, Outcome, RawTag, Reader, SanPlus, Visitor};
+use shakmaty::fen::Fen;
+use shakmaty::{CastlingMode, Chess, Color, Move, Position};
+use std::io::{self, Read};
+use std::iter;
+use std::ops::ControlFlow;
+
+/// Values of the Variant tag for games played under the usual rules
+const STANDARD_VARIANTS: [&str; 3] = ["standard", "chess", "from position"];
+
+/// A finished game
+pub struct Game {
+    /// Given by the FEN tag, or the usual start position
+    pub start: Chess,
+    pub moves: Vec<Move>,
+    /// `None` for a draw
+    pub winner: Option<Color>,
+}
+
+impl Game {
+    /// Position before each move, with the move
+    pub fn replay(&self) -> impl Iterator<Item = (Chess, Move)> + '_ {
+        self.moves.iter().scan(self.start.clone(), |position, &mv| {
+            let before = position.clone();
+            position.play_unchecked(mv);
+            Some((before, mv))
+        })
+    }
+
+    /// Every position of the game, from the start to the end
+    pub fn positions(&self) -> impl Iterator<Item = Chess> + '_ {
+        iter::once(self.start.clone()).chain(self.replay().map(|(mut position, mv)| {
+            position.play_unchecked(mv);
+            position
+        }))
+    }
+
+    /// Result from white's point of view: 1.0, 0.5 or 0.0
+    pub fn white_result(&self) -> f64 {
+        match self.winner {
+            Some(Color::White) => 1.0,
+            Some(Color::Black) => 0.0,
+            None => 0.5,
+        }
+    }
+}
+
+/// Reads the finished games of a PGN file, one by one
+pub struct Games<R> {
+    reader: Reader<R>,
+    skipped: usize,
+}
+
+impl<R: Read> Games<R> {
+    pub fn new(reader: R) -> Self {
+        Games {
+            reader: Reader::new(reader),
+            skipped: 0,
+        }
+    }
+
+    /// Games left out so far
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+}
+
+impl<R: Read> Iterator for Games<R> {
+    type Item = io::Result<Game>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.reader.read_game(&mut GameVisitor) {
+                Ok(Some(Some(game))) => return Some(Ok(game)),
+                Ok(Some(None)) => self.skipped += 1,
+                Ok(None) => return None,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
+
+/// Replays a game, `None` for games that are skipped
+struct GameVisitor;
+
+/// Game being replayed
+struct Replay {
+    position: Chess,
+    game: Game,
+    /// Whether the game has a result
+    finished: bool,
+}
+
+impl Visitor for GameVisitor {
+    /// Start position, if given by a FEN tag
+    type Tags = Option<Chess>;
+    type Movetext = Replay;
+    type Output = Option<Game>;
+
+    fn begin_tags(&mut self) -> ControlFlow<Self::Output, Self::Tags> {
+        ControlFlow::Continue(None)
+    }
+
+    fn tag(
+        &mut self,
+        tags: &mut Self::Tags,
+        name: &[u8],
+        value: RawTag<'_>,
+    ) -> ControlFlow<Self::Output> {
+        match name {
+            b"FEN" => {
+                let position = Fen::from_ascii(value.as_bytes())
+                    .ok()
+                    .and_then(|fen| fen.into_position(CastlingMode::Standard).ok());
+                match position {
+                    Some(position) => *tags = Some(position),
+                    None => return ControlFlow::Break(None),
+                }
+            }
+            b"Variant" => {
+                let variant = value.decode();
+                if !STANDARD_VARIANTS
+                    .iter()
+                    .any(|standard| variant.eq_ignore_ascii_case(standard.as_bytes()))
+                {
+                    return ControlFlow::Break(None);
+                }
+            }
+            _ => (),
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn begin_movetext(&mut self, tags: Self::Tags) -> ControlFlow<Self::Output, Self::Movetext> {
+        let start = tags.unwrap_or_default();
+        ControlFlow::Continue(Replay {
+            position: start.clone(),
+            game: Game {
+                start,
+                moves: Vec::new(),
+                winner: None,
+            },
+            finished: false,
+        })
+    }
+
+    fn san(&mut self, replay: &mut Self::Movetext, san_plus: SanPlus) -> ControlFlow<Self::Output> {
+        let Ok(mv) = san_plus.san.to_move(&replay.position) else {
+            return ControlFlow::Break(None);
+        };
+        replay.position.play_unchecked(mv);
+        replay.game.moves.push(mv);
+        ControlFlow::Continue(())
+    }
+
+    fn outcome(
+        &mut self,
+        replay: &mut Self::Movetext,
+        outcome: Outcome,
+    ) -> ControlFlow<Self::Output> {
+        if let Outcome::Known(outcome) = outcome {
+            replay.finished = true;
+            replay.game.winner = match outcome {
+                KnownOutcome::Decisive { winner } => Some(winner),
+                KnownOutcome::Draw => None,
+            };
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn end_game(&mut self, replay: Self::Movetext) -> Self::Output {
+        replay.finished.then_some(replay.game)
+    }
+}
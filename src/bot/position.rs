@@ -0,0 +1,68 @@
+//! Validation of positions sent by the user interface.
+
+use shakmaty::fen::Fen;
+use shakmaty::variant::{Variant, VariantPosition};
+use shakmaty::{CastlingMode, EnPassantMode, Position};
+use shakmaty_uci::UciMove;
+use std::fmt;
+
+/// Reasons a `position` command is rejected
+#[derive(Debug)]
+pub enum PositionError {
+    /// The FEN does not describe a legal position of the variant
+    InvalidFen { fen: String, reason: String },
+    /// A move can't be played in the position reached so far
+    IllegalMove {
+        mv: UciMove,
+        ply: usize,
+        fen: String,
+    },
+}
+
+impl fmt::Display for PositionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PositionError::InvalidFen { fen, reason } => {
+                write!(f, "invalid position '{fen}': {reason}")
+            }
+            PositionError::IllegalMove { mv, ply, fen } => {
+                write!(f, "illegal move {mv} (move {}) in '{fen}'", ply + 1)
+            }
+        }
+    }
+}
+
+/// Set up the position reached by playing `moves` from `fen`, or the start position of the variant.
+///
+/// Nothing is assumed about the input: any problem is returned as an error.
+pub fn setup_position(
+    fen: Option<Fen>,
+    moves: &[UciMove],
+    variant: Variant,
+    mode: CastlingMode,
+) -> Result<VariantPosition, PositionError> {
+    let mut position = match fen {
+        Some(fen) => {
+            let text = fen.to_string();
+            VariantPosition::from_setup(variant, fen.into_setup(), mode).map_err(|reason| {
+                PositionError::InvalidFen {
+                    fen: text,
+                    reason: reason.to_string(),
+                }
+            })?
+        }
+        None => VariantPosition::new(variant),
+    };
+
+    for (ply, mv) in moves.iter().enumerate() {
+        let illegal = |position: &VariantPosition| PositionError::IllegalMove {
+            mv: *mv,
+            ply,
+            fen: Fen::from_position(position, EnPassantMode::Legal).to_string(),
+        };
+        let m = mv.to_move(&position).map_err(|_| illegal(&position))?;
+        position = position.play(m).map_err(|err| illegal(&err.position))?;
+    }
+
+    Ok(position)
+}
@@ -0,0 +1,496 @@
+//! Front end for the xboard protocol (CECP) version 2, as an alternative to UCI.
+//!
+//! Commands are translated into the same `SearchCommand`s the UCI controller sends, so both front
+//! ends share the searcher. See <https://www.gnu.org/software/xboard/engine-intf.html>.
+
+use crate::bot::crash;
+use crate::bot::input::read_line;
+use crate::bot::log::{FileLogger, Level, Logger};
+use crate::bot::position::setup_position;
+use crate::search::eval::Handcrafted;
+use crate::search::nnue::{Network, NnueState};
+use crate::search::Evaluator;
+use crate::{SearchCommand, SearchControl, SearchInfo, SEARCH_TIME_MS};
+use chrono::Local;
+use crossbeam_channel::{select, Receiver, Sender};
+use shakmaty::fen::Fen;
+use shakmaty::san::SanPlus;
+use shakmaty::variant::{Variant, VariantPosition};
+use shakmaty::{CastlingMode, Color, EnPassantMode, KnownOutcome, Move, Outcome, Position};
+use shakmaty_uci::UciMove;
+use std::io::{self, Write};
+use std::sync::Arc;
+
+// Parameters
+/// How long an `analyze` search may take
+const ANALYZE_TIME_MS: u64 = 60_000;
+/// Moves assumed left in the game when the time control has no move count
+const DEFAULT_MOVES_TO_GO: u64 = 30;
+/// Shortest time spent on a move when playing on a clock
+const MIN_MOVE_TIME_MS: u64 = 10;
+/// Largest score shown in thinking output, xboard reads larger values as mate scores
+const MAX_SHOWN_SCORE: i32 = 99_999;
+
+/// Reads lines from stdin and forwards them to the line channel
+pub fn listen(line_tx: Sender<String>) {
+    let mut stdin = io::stdin().lock();
+
+    // Listen while stdin is open
+    while let Some(line) = read_line(&mut stdin) {
+        let trimmed = line.trim();
+        if !trimmed.is_empty() {
+            line_tx.send(trimmed.to_string()).unwrap();
+        }
+    }
+}
+
+/// Time allowed for moves, set by `level`, `st`, `sd` and `time`
+#[derive(Default)]
+struct TimeControl {
+    /// Moves per time control period, 0 if the whole game is one period
+    moves_per_period: u64,
+    increment_ms: u64,
+    /// Fixed time per move
+    move_time_ms: Option<u64>,
+    depth: Option<u8>,
+    /// Time left on the engine's clock
+    clock_ms: Option<u64>,
+}
+
+impl TimeControl {
+    /// How the searcher should be limited for a move in `position`
+    fn search_control(&self, position: &VariantPosition) -> SearchControl {
+        if let Some(move_time) = self.move_time_ms {
+            return SearchControl::TimeLimit(move_time);
+        }
+        if let Some(clock) = self.clock_ms {
+            let moves_to_go = match self.moves_per_period {
+                0 => DEFAULT_MOVES_TO_GO,
+                moves => moves - (position.fullmoves().get() as u64 - 1) % moves,
+            };
+            let time = clock / moves_to_go + self.increment_ms * 3 / 4;
+            return SearchControl::TimeLimit(
+                time.clamp(MIN_MOVE_TIME_MS, clock.max(MIN_MOVE_TIME_MS)),
+            );
+        }
+        match self.depth {
+            Some(depth) => SearchControl::ToDepth(depth),
+            None => SearchControl::TimeLimit(SEARCH_TIME_MS),
+        }
+    }
+}
+
+/// Why the searcher is busy
+enum Search {
+    /// Thinking about a move to play
+    Move,
+    /// Analysing the position without playing
+    Analyze,
+}
+
+/// Handles xboard commands, plays the engine's moves and produces runtime logs.
+///
+/// Lines are written to `output`, standard output when run as an engine.
+pub struct XBoardController<W: Write = io::Stdout, L: Logger = FileLogger> {
+    line_rx: Receiver<String>,
+    cmd_tx: Sender<SearchCommand>,
+    info_rx: Receiver<SearchInfo>,
+    output: W,
+    logger: L,
+    position: VariantPosition,
+    /// Positions before each move, for `undo` and `remove`
+    history: Vec<VariantPosition>,
+    /// Side the engine plays, `None` in force mode
+    engine_color: Option<Color>,
+    analyzing: bool,
+    /// Whether to show thinking output
+    post: bool,
+    time: TimeControl,
+    search: Option<Search>,
+    /// Searches that were started but whose results are no longer wanted
+    stale_searches: u32,
+}
+
+impl<W: Write, L: Logger> XBoardController<W, L> {
+    pub fn new(
+        line_rx: Receiver<String>,
+        cmd_tx: Sender<SearchCommand>,
+        info_rx: Receiver<SearchInfo>,
+        output: W,
+        logger: L,
+    ) -> Self {
+        let controller = XBoardController {
+            line_rx,
+            cmd_tx,
+            info_rx,
+            output,
+            logger,
+            position: VariantPosition::default(),
+            history: Vec::new(),
+            engine_color: Some(Color::Black),
+            analyzing: false,
+            post: false,
+            time: TimeControl::default(),
+            search: None,
+            stale_searches: 0,
+        };
+
+        let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S");
+        controller.log(
+            Level::Info,
+            &format!("------ Engine started at {} (xboard) ------", timestamp),
+        );
+
+        // Use the network if there is one, like the UCI front end does by default
+        let evaluator: Box<dyn Evaluator> = match Network::embedded() {
+            Some(network) => Box::new(NnueState::new(Arc::new(network))),
+            None => Box::new(Handcrafted::default()),
+        };
+        controller
+            .cmd_tx
+            .send(SearchCommand::SetEvaluator(evaluator))
+            .unwrap();
+
+        controller
+    }
+
+    /// Runs the controller.
+    pub fn run(mut self) {
+        loop {
+            select! {
+                recv(self.line_rx) -> line => {
+                    let Ok(line) = line else { break };
+                    self.log(Level::Info, &format!(" IN: '{}'", line));
+                    crash::record_command(&line);
+                    if self.handle_command(&line) {
+                        break;
+                    }
+                    crash::record_position(
+                        Fen::from_position(&self.position, EnPassantMode::Legal).to_string(),
+                    );
+                }
+
+                recv(self.info_rx) -> info => self.handle_info(info.unwrap()),
+            }
+        }
+        self.cmd_tx.send(SearchCommand::Quit).unwrap();
+    }
+
+    /// Sends an outbound line
+    fn send(&mut self, line: &str) {
+        writeln!(self.output, "{line}")
+            .and_then(|_| self.output.flush())
+            .expect("failed to write to the user interface");
+        self.log(Level::Info, &format!("OUT: '{}'", line));
+    }
+
+    /// Handles a command from the user interface, returns true on `quit`
+    fn handle_command(&mut self, line: &str) -> bool {
+        let (command, args) = line.split_once(' ').unwrap_or((line, ""));
+        let args = args.trim();
+
+        match command {
+            // Announce what the engine supports
+            "protover" => {
+                let features = [
+                    format!("myname=\"{}\"", env!("CARGO_PKG_NAME")),
+                    "ping=1".into(),
+                    "setboard=1".into(),
+                    "usermove=1".into(),
+                    "analyze=1".into(),
+                    "colors=0".into(),
+                    "sigint=0".into(),
+                    "sigterm=0".into(),
+                    "variants=\"normal\"".into(),
+                ];
+                self.send(&format!("feature {}", features.join(" ")));
+                self.send("feature done=1");
+            }
+
+            // Start a new game with the engine playing black
+            "new" => {
+                self.cancel_search();
+                self.position = VariantPosition::new(Variant::Chess);
+                self.history.clear();
+                self.engine_color = Some(Color::Black);
+                self.time.depth = None;
+                self.time.move_time_ms = None;
+            }
+
+            // Stop playing moves, only follow the moves of the game
+            "force" => {
+                self.cancel_search();
+                self.engine_color = None;
+            }
+
+            // Play the side to move, starting now
+            "go" => {
+                self.engine_color = Some(self.position.turn());
+                self.think();
+            }
+
+            // Play the side not to move, starting after the next move
+            "playother" => {
+                self.cancel_search();
+                self.engine_color = Some(self.position.turn().other());
+            }
+
+            "usermove" => self.user_move(args),
+
+            "setboard" => {
+                self.cancel_search();
+                let position = args.parse::<Fen>().ok().and_then(|fen| {
+                    setup_position(Some(fen), &[], Variant::Chess, CastlingMode::Standard).ok()
+                });
+                match position {
+                    Some(position) => {
+                        self.position = position;
+                        self.history.clear();
+                        self.restart_analysis();
+                    }
+                    None => self.send("tellusererror Illegal position"),
+                }
+            }
+
+            // Take back one or two moves
+            "undo" => self.take_back(1),
+            "remove" => self.take_back(2),
+
+            // Time controls
+            "level" => match parse_level(args) {
+                Some((moves, base_ms, increment_ms)) => {
+                    self.time.moves_per_period = moves;
+                    self.time.increment_ms = increment_ms;
+                    self.time.clock_ms = Some(base_ms);
+                    self.time.move_time_ms = None;
+                }
+                None => self.send(&format!("Error (bad arguments): {line}")),
+            },
+            "st" => match args.parse::<u64>() {
+                Ok(seconds) => self.time.move_time_ms = Some(seconds * 1000),
+                Err(_) => self.send(&format!("Error (bad arguments): {line}")),
+            },
+            "sd" => match args.parse::<u8>() {
+                Ok(depth) => self.time.depth = Some(depth),
+                Err(_) => self.send(&format!("Error (bad arguments): {line}")),
+            },
+            // Clocks are given in centiseconds
+            "time" => match args.parse::<u64>() {
+                Ok(centis) => self.time.clock_ms = Some(centis * 10),
+                Err(_) => self.send(&format!("Error (bad arguments): {line}")),
+            },
+            "otim" => (),
+
+            // Thinking output
+            "post" => self.post = true,
+            "nopost" => self.post = false,
+
+            // Analysis mode, the engine analyses every position without playing
+            "analyze" => {
+                self.cancel_search();
+                self.engine_color = None;
+                self.analyzing = true;
+                self.think();
+            }
+            "exit" => {
+                self.cancel_search();
+                self.analyzing = false;
+            }
+
+            "ping" => self.send(&format!("pong {args}")),
+
+            // The game is over
+            "result" => {
+                self.cancel_search();
+                self.engine_color = None;
+            }
+
+            "quit" => return true,
+
+            // Accepted but not needed
+            "xboard" | "accepted" | "rejected" | "random" | "hard" | "easy" | "computer"
+            | "name" | "rating" | "ics" | "." | "?" => (),
+
+            // Moves without `usermove`, in case the feature was not accepted
+            _ if line.parse::<UciMove>().is_ok() => self.user_move(line),
+
+            _ => {
+                self.log(Level::Warn, &format!("ERR: 'unknown command: {}'", line));
+                self.send(&format!("Error (unknown command): {command}"));
+            }
+        }
+        false
+    }
+
+    /// Plays a move of the opponent, and answers it if it's the engine's turn
+    fn user_move(&mut self, text: &str) {
+        let mv = text
+            .parse::<UciMove>()
+            .ok()
+            .and_then(|mv| mv.to_move(&self.position).ok());
+        let Some(mv) = mv else {
+            self.send(&format!("Illegal move: {text}"));
+            return;
+        };
+
+        self.cancel_search();
+        self.play(mv);
+
+        if self.analyzing {
+            self.restart_analysis();
+        } else if self.engine_color == Some(self.position.turn()) {
+            self.think();
+        }
+    }
+
+    fn play(&mut self, mv: Move) {
+        self.history.push(self.position.clone());
+        self.position.play_unchecked(mv);
+        self.report_result();
+    }
+
+    /// Claims the result if the game is over
+    fn report_result(&mut self) {
+        let message = match self.position.outcome() {
+            Outcome::Known(KnownOutcome::Decisive { winner }) => {
+                let score = winner.fold_wb("1-0", "0-1");
+                let reason = match (self.position.is_checkmate(), winner) {
+                    (true, Color::White) => "White mates",
+                    (true, Color::Black) => "Black mates",
+                    (false, Color::White) => "White wins",
+                    (false, Color::Black) => "Black wins",
+                };
+                format!("{score} {{{reason}}}")
+            }
+            Outcome::Known(KnownOutcome::Draw) if self.position.is_stalemate() => {
+                "1/2-1/2 {Stalemate}".to_string()
+            }
+            Outcome::Known(KnownOutcome::Draw) => "1/2-1/2 {Draw}".to_string(),
+            Outcome::Unknown => return,
+        };
+        self.send(&message);
+    }
+
+    fn take_back(&mut self, moves: usize) {
+        self.cancel_search();
+        for _ in 0..moves {
+            if let Some(position) = self.history.pop() {
+                self.position = position;
+            }
+        }
+        self.restart_analysis();
+    }
+
+    /// Starts searching the current position, unless the game is over
+    fn think(&mut self) {
+        if self.position.is_game_over() {
+            return;
+        }
+        self.cancel_search();
+
+        let (search, control) = if self.analyzing {
+            (Search::Analyze, SearchControl::TimeLimit(ANALYZE_TIME_MS))
+        } else {
+            (Search::Move, self.time.search_control(&self.position))
+        };
+        self.search = Some(search);
+        self.cmd_tx
+            .send(SearchCommand::Start {
+                position: self.position.clone(),
+                control,
+            })
+            .unwrap();
+    }
+
+    fn restart_analysis(&mut self) {
+        if self.analyzing {
+            self.think();
+        }
+    }
+
+    /// Forget about the current search, its result will be ignored
+    fn cancel_search(&mut self) {
+        if self.search.take().is_some() {
+            self.stale_searches += 1;
+            self.cmd_tx.send(SearchCommand::Stop).unwrap();
+        }
+    }
+
+    fn handle_info(&mut self, message: SearchInfo) {
+        match message {
+            SearchInfo::BestMove(mv) => {
+                if self.stale_searches > 0 {
+                    self.stale_searches -= 1;
+                    return;
+                }
+                // Play the move, analysis only shows its thinking, and there is none if the game is over
+                if let (Some(Search::Move), Some(mv)) = (self.search.take(), mv) {
+                    let uci = UciMove::from_move(mv, CastlingMode::Standard);
+                    self.send(&format!("move {uci}"));
+                    self.play(mv);
+                }
+            }
+
+            // Show thinking as `ply score time nodes pv`, with time in centiseconds
+            SearchInfo::Info {
+                depth,
+                multipv: 1,
+                pv,
+                score,
+                nodes,
+                time,
+                ..
+            } => {
+                if self.search.is_none()
+                    || self.stale_searches > 0
+                    || !(self.post || self.analyzing)
+                {
+                    return;
+                }
+                let centis = time.as_millis() / 10;
+                let score = score.clamp(-MAX_SHOWN_SCORE, MAX_SHOWN_SCORE);
+                let pv = san_line(&self.position, &pv);
+                self.send(&format!("{depth} {score} {centis} {nodes} {pv}"));
+            }
+
+            SearchInfo::Info { .. } | SearchInfo::CurrMove { .. } => (),
+        }
+    }
+
+    fn log(&self, level: Level, line: &str) {
+        self.logger.log(level, line);
+    }
+}
+
+impl<W: Write, L: Logger> Drop for XBoardController<W, L> {
+    fn drop(&mut self) {
+        self.log(Level::Info, "------ Engine closed ------");
+    }
+}
+
+/// Parse `level MPS BASE INC`, where the base time is in minutes, or minutes and seconds
+/// separated by a colon. Returns (moves, base in ms, increment in ms).
+fn parse_level(args: &str) -> Option<(u64, u64, u64)> {
+    let mut tokens = args.split_whitespace();
+    let moves = tokens.next()?.parse().ok()?;
+
+    let base = tokens.next()?;
+    let base_ms = match base.split_once(':') {
+        Some((minutes, seconds)) => {
+            minutes.parse::<u64>().ok()? * 60_000 + seconds.parse::<u64>().ok()? * 1000
+        }
+        None => base.parse::<u64>().ok()? * 60_000,
+    };
+
+    let increment: f64 = tokens.next()?.parse().ok()?;
+    Some((moves, base_ms, (increment * 1000.0) as u64))
+}
+
+/// Moves of a principal variation in standard algebraic notation
+fn san_line(position: &VariantPosition, pv: &[Move]) -> String {
+    let mut position = position.clone();
+    pv.iter()
+        .map(|&mv| SanPlus::from_move_and_play_unchecked(&mut position, mv).to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
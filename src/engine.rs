@@ -0,0 +1,214 @@
+//! Synchronous interface to the engine for use from Rust code, without a protocol or channels.
+
+use crate::search::eval::Handcrafted;
+use crate::search::tablebase::Tablebases;
+use crate::search::{Evaluator, Limits, SearchAlgorithm, SearchOptions, SearchState};
+use crate::SearchInfo;
+use crossbeam_channel::{unbounded, Receiver};
+use shakmaty::variant::VariantPosition;
+use shakmaty::{Chess, Move};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+
+/// Result of a search
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Analysis {
+    /// `None` if there are no legal moves
+    pub best_move: Option<Move>,
+    /// Principal variation, starting with the best move
+    pub pv: Vec<Move>,
+    /// Score in centipawns from the point of view of the side to move
+    pub score: i32,
+    pub nodes: u64,
+    /// Deepest completed iteration
+    pub depth: u8,
+}
+
+impl Analysis {
+    /// Summarize the info updates of a search, the last update of the best line is the result
+    fn new(best_move: Option<Move>, infos: impl IntoIterator<Item = SearchInfo>) -> Self {
+        let mut analysis = Analysis {
+            best_move,
+            pv: best_move.into_iter().collect(),
+            score: 0,
+            nodes: 0,
+            depth: 0,
+        };
+        for info in infos {
+            if let SearchInfo::Info {
+                depth,
+                multipv: 1,
+                pv,
+                score,
+                nodes,
+                ..
+            } = info
+            {
+                analysis.pv = pv;
+                analysis.score = score;
+                analysis.nodes = nodes;
+                analysis.depth = depth;
+            }
+        }
+        analysis
+    }
+}
+
+/// The engine, searching on the calling thread.
+///
+/// ```no_run
+/// use kaksic::search::Limits;
+/// use kaksic::Engine;
+///
+/// let mut engine = Engine::new();
+/// let analysis = engine.analyse(&shakmaty::Chess::default(), Limits::depth(3));
+/// if let Some(best_move) = analysis.best_move {
+///     println!("{} ({} cp)", best_move, analysis.score);
+/// }
+/// ```
+pub struct Engine {
+    /// Only missing while a streaming search borrows it
+    state: Option<SearchState>,
+}
+
+impl Engine {
+    /// An engine with the handcrafted evaluation and default options
+    pub fn new() -> Self {
+        Self::with_evaluator(Box::new(Handcrafted::default()))
+    }
+
+    pub fn with_evaluator(evaluator: Box<dyn Evaluator>) -> Self {
+        Engine {
+            state: Some(SearchState::new(evaluator)),
+        }
+    }
+
+    pub fn set_evaluator(&mut self, evaluator: Box<dyn Evaluator>) {
+        self.state().set_evaluator(evaluator);
+    }
+
+    pub fn set_algorithm(&mut self, algorithm: Box<dyn SearchAlgorithm>) {
+        self.state().set_algorithm(algorithm);
+    }
+
+    pub fn set_options(&mut self, options: SearchOptions) {
+        self.state().set_options(options);
+    }
+
+    /// Forget everything learned in earlier searches
+    pub fn clear_hash(&mut self) {
+        self.state().clear_hash();
+    }
+
+    /// Use endgame tablebases, or stop using them
+    pub fn set_tablebases(&mut self, tablebases: Option<Tablebases>) {
+        self.state().set_tablebases(tablebases);
+    }
+
+    /// Search `position` within `limits` and wait for the result
+    pub fn analyse(&mut self, position: &Chess, limits: Limits) -> Analysis {
+        let position = VariantPosition::from(position.clone());
+        let (info_tx, info_rx) = unbounded();
+        let best_move = self.state().search(&position, &restart(limits), &info_tx);
+        drop(info_tx);
+        Analysis::new(best_move, info_rx)
+    }
+
+    /// Search `position` within `limits` in the background, yielding info updates as they come.
+    ///
+    /// The updates end with the best move, `Analysing::finish` gives the result.
+    pub fn analyse_iter(&mut self, position: &Chess, limits: Limits) -> Analysing<'_> {
+        let position = VariantPosition::from(position.clone());
+        let mut state = self.state.take().expect("engine is not searching");
+        let (info_tx, info_rx) = unbounded();
+        let limits = restart(limits);
+        let stop = limits.stop.clone();
+
+        let search = thread::spawn(move || {
+            let best_move = state.search(&position, &limits, &info_tx);
+            info_tx.send(SearchInfo::BestMove(best_move)).unwrap();
+            state
+        });
+
+        Analysing {
+            engine: self,
+            info_rx,
+            stop,
+            search: Some(search),
+            best_move: None,
+            best_line: None,
+        }
+    }
+
+    fn state(&mut self) -> &mut SearchState {
+        self.state.as_mut().expect("engine is not searching")
+    }
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Limits measured from now, rather than from when they were created
+fn restart(limits: Limits) -> Limits {
+    Limits {
+        start: Instant::now(),
+        ..limits
+    }
+}
+
+/// A search running in the background, see `Engine::analyse_iter`
+pub struct Analysing<'a> {
+    engine: &'a mut Engine,
+    info_rx: Receiver<SearchInfo>,
+    /// Ends the search early, when the updates are no longer wanted
+    stop: Arc<AtomicBool>,
+    search: Option<JoinHandle<SearchState>>,
+    /// Set once the search has ended
+    best_move: Option<Option<Move>>,
+    /// Latest update of the best line
+    best_line: Option<SearchInfo>,
+}
+
+impl Analysing<'_> {
+    /// Wait for the search to end and summarize it
+    pub fn finish(mut self) -> Analysis {
+        self.by_ref().for_each(drop);
+        let best_move = self.best_move.expect("search ended without a best move");
+        Analysis::new(best_move, self.best_line.take())
+    }
+
+    /// Give the search state back to the engine once the search is done
+    fn join(&mut self) {
+        if let Some(search) = self.search.take() {
+            self.engine.state = Some(search.join().expect("search thread panicked"));
+        }
+    }
+}
+
+impl Iterator for Analysing<'_> {
+    type Item = SearchInfo;
+
+    fn next(&mut self) -> Option<SearchInfo> {
+        let info = self.info_rx.recv().ok();
+        match &info {
+            Some(SearchInfo::BestMove(mv)) => self.best_move = Some(*mv),
+            Some(line @ SearchInfo::Info { multipv: 1, .. }) => self.best_line = Some(line.clone()),
+            Some(SearchInfo::Info { .. } | SearchInfo::CurrMove { .. }) => (),
+            None => self.join(),
+        }
+        info
+    }
+}
+
+impl Drop for Analysing<'_> {
+    fn drop(&mut self) {
+        // Nobody reads the rest of the updates, so there is no point in finishing the search
+        self.stop.store(true, Ordering::Relaxed);
+        self.join();
+    }
+}
@@ -1,6 +1,9 @@
 pub mod bot;
+mod engine;
 pub mod search;
 
+pub use engine::{Analysing, Analysis, Engine};
+
 // Parameters
 const SEARCH_TIME_MS: u64 = 2000;
 
@@ -15,20 +18,49 @@ pub enum SearchControl {
 /// Instructions for the search thread
 pub enum SearchCommand {
     Start {
-        position: shakmaty::Chess,
+        position: shakmaty::variant::VariantPosition,
         control: SearchControl,
     },
+    // Replace the static evaluation used by the search
+    SetEvaluator(Box<dyn search::Evaluator>),
+    // Replace the algorithm used to search
+    SetAlgorithm(Box<dyn search::SearchAlgorithm>),
+    // Change the settings of the search
+    SetOptions(search::SearchOptions),
+    // Forget everything learned in earlier searches
+    ClearHash,
+    // Replace the endgame tablebases, or stop using them
+    SetTablebases(Option<search::tablebase::Tablebases>),
     Stop,
     Quit,
 }
 
 /// Search information to be logged
+#[derive(Clone, Debug)]
 pub enum SearchInfo {
-    BestMove(shakmaty::Move),
+    // `None` if there are no legal moves
+    BestMove(Option<shakmaty::Move>),
     Info {
         depth: u8,
+        // Deepest ply reached
+        seldepth: u8,
+        // Rank of the line, starting from 1
+        multipv: u8,
         pv: Vec<shakmaty::Move>,
         score: i32,
+        // Nodes searched since the search started
         nodes: u64,
+        // Time since the search started
+        time: std::time::Duration,
+        // Permille of the transposition table in use, if the algorithm uses it
+        hashfull: Option<u16>,
+        // Positions found in the endgame tablebases
+        tbhits: u64,
+    },
+    // Root move being searched, numbered from 1
+    CurrMove {
+        depth: u8,
+        mv: shakmaty::Move,
+        number: u16,
     },
 }
@@ -1,20 +1,79 @@
 use crossbeam_channel::unbounded;
-use kaksic::bot::{controller::Controller, input::InputListener};
+use kaksic::bot::input::{self, InputListener};
+use kaksic::bot::log::FileLogger;
+use kaksic::bot::{controller::Controller, crash, xboard, xboard::XBoardController};
+use kaksic::search::bench::{self, BENCH_DEPTH};
+use kaksic::search::eval::Handcrafted;
 use kaksic::search::Searcher;
-use std::thread;
+use std::{env, io, thread};
+
+// Parameters
+const LOG_FILE: &str = "engine.log";
 
 fn main() {
+    // Run the bench instead of the engine: `kaksic bench [depth]`
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("bench") {
+        let depth = args.get(1).map_or(BENCH_DEPTH, |depth| {
+            depth.parse().expect("Invalid bench depth")
+        });
+        println!("{}", bench::run(depth));
+        return;
+    }
+
+    // Report panics of any thread
+    crash::install(LOG_FILE);
+
+    // Pick the protocol with `--xboard`, or from the first command the user interface sends
+    let (xboard, first_line) = if args.iter().any(|arg| arg == "--xboard") {
+        (true, None)
+    } else {
+        match input::read_line(&mut io::stdin().lock()) {
+            Some(line) => (line.trim() == "xboard", Some(line)),
+            None => return,
+        }
+    };
+
     // Initialize channels
-    let (input_tx, input_rx) = unbounded();
     let (cmd_tx, cmd_rx) = unbounded();
     let (info_tx, info_rx) = unbounded();
 
-    // Spawn input listener thread
-    thread::spawn(|| InputListener::new(input_tx).run());
-
     // Spawn search thread
-    thread::spawn(|| Searcher::new(cmd_rx, info_tx).run());
+    thread::spawn(|| Searcher::new(cmd_rx, info_tx, Box::new(Handcrafted::default())).run());
+
+    if xboard {
+        // Spawn input listener thread, the `xboard` command itself needs no answer
+        let (line_tx, line_rx) = unbounded();
+        thread::spawn(|| xboard::listen(line_tx));
+
+        // Run controller on main thread
+        XBoardController::new(
+            line_rx,
+            cmd_tx,
+            info_rx,
+            io::stdout(),
+            FileLogger::new(LOG_FILE),
+        )
+        .run();
+    } else {
+        // Spawn input listener thread, starting with the line read to detect the protocol
+        let (input_tx, input_rx) = unbounded();
+        thread::spawn(move || {
+            let listener = InputListener::new(input_tx);
+            if let Some(line) = first_line {
+                listener.forward(&line);
+            }
+            listener.run()
+        });
 
-    // Run controller on main thread
-    Controller::new(input_rx, cmd_tx, info_rx, "engine.log").run();
+        // Run controller on main thread
+        Controller::new(
+            input_rx,
+            cmd_tx,
+            info_rx,
+            io::stdout(),
+            FileLogger::new(LOG_FILE),
+        )
+        .run();
+    }
 }
@@ -0,0 +1,91 @@
+use crate::search::tablebase::Tablebases;
+use crate::search::tt::TranspositionTable;
+use crate::search::{Evaluator, SearchOptions};
+use crate::{SearchControl, SearchInfo};
+use crossbeam_channel::Sender;
+use shakmaty::variant::VariantPosition;
+use shakmaty::Move;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Constraints of a single search
+pub struct Limits {
+    /// Deepest iteration to search, if limited by depth
+    pub depth: Option<u8>,
+    /// Approximate duration of the search, if limited by time
+    pub time: Option<Duration>,
+    /// When the search started
+    pub start: Instant,
+    /// Set to end the search early, with the best move found so far
+    pub stop: Arc<AtomicBool>,
+}
+
+impl Limits {
+    pub fn new(control: &SearchControl) -> Self {
+        let (depth, time) = match *control {
+            SearchControl::ToDepth(depth) => (Some(depth), None),
+            SearchControl::TimeLimit(time_limit) => (None, Some(Duration::from_millis(time_limit))),
+        };
+
+        Limits {
+            depth,
+            time,
+            start: Instant::now(),
+            stop: Arc::default(),
+        }
+    }
+
+    /// Search until the given depth is completed
+    pub fn depth(depth: u8) -> Self {
+        Limits {
+            depth: Some(depth),
+            time: None,
+            start: Instant::now(),
+            stop: Arc::default(),
+        }
+    }
+
+    /// Search for about the given duration
+    pub fn time(time: Duration) -> Self {
+        Limits {
+            depth: None,
+            time: Some(time),
+            start: Instant::now(),
+            stop: Arc::default(),
+        }
+    }
+
+    /// Whether the time given to the search has run out
+    pub fn time_up(&self) -> bool {
+        self.time.is_some_and(|time| self.start.elapsed() >= time)
+    }
+
+    /// Whether the search was told to stop
+    pub fn stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+}
+
+/// State shared by all searches, borrowed from the `SearchState`
+pub struct SearchContext<'a> {
+    /// Reset to the root position, and must be left in that state
+    pub evaluator: &'a mut dyn Evaluator,
+    pub tt: &'a mut TranspositionTable,
+    pub options: &'a SearchOptions,
+    /// Endgame tablebases, if loaded and the position is standard chess
+    pub tablebases: Option<&'a Tablebases>,
+    pub info_tx: &'a Sender<SearchInfo>,
+}
+
+/// A way of searching for the best move
+pub trait SearchAlgorithm: Send {
+    /// Search `position` within `limits`, reporting progress on the info channel, and return the best move.
+    /// `position` has at least one legal move.
+    fn search(
+        &mut self,
+        position: &VariantPosition,
+        limits: &Limits,
+        ctx: &mut SearchContext,
+    ) -> Move;
+}
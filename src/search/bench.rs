@@ -0,0 +1,141 @@
+//! Fixed depth search of fixed positions.
+//!
+//! The result is a signature of the search: any change to how it behaves changes it, while
+//! changes that only affect speed keep it. The node count catches changes to which nodes are
+//! visited, such as to move generation or the use of the transposition table. The search is full
+//! width, so the node count doesn't depend on the evaluation, and the best move and score of every
+//! position are hashed into the signature as well to catch changes to it.
+
+use crate::search::eval::Handcrafted;
+use crate::search::negamax::Negamax;
+use crate::search::tt::TranspositionTable;
+use crate::search::{Limits, SearchAlgorithm, SearchContext, SearchOptions};
+use crate::{SearchControl, SearchInfo};
+use crossbeam_channel::unbounded;
+use shakmaty::fen::Fen;
+use shakmaty::uci::UciMove;
+use shakmaty::variant::VariantPosition;
+use shakmaty::{CastlingMode, Chess};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+// Parameters
+pub const BENCH_DEPTH: u8 = 3;
+const BENCH_POSITIONS: usize = 20;
+
+static FENS: &str = include_str!("../../assets/fens.txt");
+
+/// Outcome of a bench run
+pub struct BenchResult {
+    pub nodes: u64,
+    /// Hash of the best move and score of every position
+    pub signature: u64,
+    pub elapsed: Duration,
+}
+
+impl BenchResult {
+    pub fn nps(&self) -> u64 {
+        (self.nodes as f64 / self.elapsed.as_secs_f64().max(1e-9)) as u64
+    }
+}
+
+impl fmt::Display for BenchResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Total time (ms) : {}", self.elapsed.as_millis())?;
+        writeln!(f, "Nodes searched  : {}", self.nodes)?;
+        writeln!(f, "Signature       : {:016x}", self.signature)?;
+        write!(f, "Nodes/second    : {}", self.nps())
+    }
+}
+
+/// The positions searched by the bench
+pub fn positions() -> Vec<Chess> {
+    FENS.lines()
+        .filter(|line| !line.trim().is_empty())
+        .take(BENCH_POSITIONS)
+        .map(|line| {
+            line.parse::<Fen>()
+                .unwrap()
+                .into_position(CastlingMode::Standard)
+                .unwrap()
+        })
+        .collect()
+}
+
+/// Search every bench position to `depth` on the current thread
+pub fn run(depth: u8) -> BenchResult {
+    search(&positions(), depth)
+}
+
+/// Search each of `positions` to `depth` on the current thread.
+///
+/// Every search starts from the same state, with default options and the handcrafted
+/// evaluation, so the result only depends on the search itself.
+pub fn search(positions: &[Chess], depth: u8) -> BenchResult {
+    let options = SearchOptions::default();
+    let (info_tx, info_rx) = unbounded();
+    let mut tt = TranspositionTable::new(options.hash_mb);
+    let mut nodes = 0;
+    let mut signature = FNV_OFFSET;
+    let mut elapsed = Duration::ZERO;
+
+    for position in positions {
+        let position = VariantPosition::from(position.clone());
+        let mut evaluator = Handcrafted::default();
+        tt.clear();
+        let mut ctx = SearchContext {
+            evaluator: &mut evaluator,
+            tt: &mut tt,
+            options: &options,
+            tablebases: None,
+            info_tx: &info_tx,
+        };
+        ctx.evaluator.reset(&position);
+
+        let start = Instant::now();
+        let best_move = Negamax.search(
+            &position,
+            &Limits::new(&SearchControl::ToDepth(depth)),
+            &mut ctx,
+        );
+        elapsed += start.elapsed();
+
+        // The last iteration reports the nodes of the whole search, and the score
+        let (mut searched, mut score) = (0, 0);
+        for info in info_rx.try_iter() {
+            if let SearchInfo::Info {
+                multipv: 1,
+                nodes: iteration_nodes,
+                score: iteration_score,
+                ..
+            } = info
+            {
+                searched = iteration_nodes;
+                score = iteration_score;
+            }
+        }
+        nodes += searched;
+
+        let uci = UciMove::from_standard(best_move).to_string();
+        signature = fnv1a(signature, uci.as_bytes());
+        signature = fnv1a(signature, &score.to_le_bytes());
+    }
+
+    BenchResult {
+        nodes,
+        signature,
+        elapsed,
+    }
+}
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// FNV-1a, a hash that is the same on every platform and in every Rust version
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for &byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
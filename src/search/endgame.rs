@@ -0,0 +1,321 @@
+//! Knowledge of simple endgames in standard chess.
+//!
+//! King and pawn against king is looked up in a bitbase generated on first use. A lone king is
+//! driven to the edge, or to the corner the bishop can cover with bishop and knight, and endings
+//! that are drawn despite extra material are scaled down.
+
+use crate::search::eval::Score;
+use crate::search::variant::HasVariant;
+use shakmaty::variant::Variant;
+use shakmaty::{attacks, Bitboard, Board, Color, File, Rank, Role, Square};
+use std::sync::OnceLock;
+
+// Parameters
+/// Scale of a normal position, the evaluation is multiplied by `scale / SCALE_NORMAL`
+pub const SCALE_NORMAL: i32 = 64;
+/// Scale of bishops of opposite colors with only pawns besides
+const SCALE_OPPOSITE_BISHOPS: i32 = 24;
+// The bonuses below are on the scale of `eval::DEFAULT_PARAMS`, where a pawn is worth 1 and a
+// piece in the center 200, so they outweigh where the pieces stand
+/// Bonus for a king and pawn against king ending the bitbase says is won, more than the three
+/// pieces can gain or lose by their placement
+const KPK_WIN: Score = Score::new(0, 1000);
+/// Bonus per square the lone king is pushed away from the center, or towards the right corner
+const MOP_UP_EDGE: Score = Score::new(0, 400);
+/// Bonus per square the kings are closer together, more than the stronger king gives up by
+/// stepping out of the center
+const MOP_UP_KINGS: Score = Score::new(0, 250);
+
+/// Endgame score of one side
+pub fn eval_endgame<P: HasVariant>(position: &P, color: Color) -> Score {
+    if position.variant() != Variant::Chess {
+        return Score::default();
+    }
+    let board = position.board();
+
+    if let Some(win) = kpk_win(board, position.turn()) {
+        return if win == color {
+            KPK_WIN
+        } else {
+            Score::default()
+        };
+    }
+
+    mop_up(board, color)
+}
+
+/// Factor the evaluation is scaled by, out of `SCALE_NORMAL`, lower for drawish endings
+pub fn scale_factor<P: HasVariant>(position: &P) -> i32 {
+    if position.variant() != Variant::Chess {
+        return SCALE_NORMAL;
+    }
+    let board = position.board();
+
+    if is_kpk(board) && kpk_win(board, position.turn()).is_none() {
+        return 0;
+    }
+    if Color::ALL
+        .into_iter()
+        .any(|color| is_wrong_bishop(board, color))
+    {
+        return 0;
+    }
+    if is_opposite_bishops(board) {
+        return SCALE_OPPOSITE_BISHOPS;
+    }
+    SCALE_NORMAL
+}
+
+/// Number of pieces of a role other than pawns and kings
+fn pieces(board: &Board, color: Color) -> u32 {
+    (board.by_color(color) & !board.pawns() & !board.kings()).count() as u32
+}
+
+fn is_lone_king(board: &Board, color: Color) -> bool {
+    board.by_color(color).count() == 1
+}
+
+fn is_kpk(board: &Board) -> bool {
+    board.occupied().count() == 3 && board.pawns().count() == 1
+}
+
+/// Winner of a king and pawn against king ending, `None` if it is a draw or another ending
+fn kpk_win(board: &Board, turn: Color) -> Option<Color> {
+    if !is_kpk(board) {
+        return None;
+    }
+    let pawn = board.pawns().first()?;
+    let strong = board.color_at(pawn)?;
+
+    // Seen from the pawn's side, with the pawn on the queenside
+    let normalize = |sq: Square| {
+        let sq = strong.fold_wb(sq, sq.flip_vertical());
+        if pawn.file() >= File::E {
+            sq.flip_horizontal()
+        } else {
+            sq
+        }
+    };
+    let index = kpk_index(
+        turn == strong,
+        normalize(board.king_of(strong)?),
+        normalize(board.king_of(!strong)?),
+        normalize(pawn),
+    );
+    (kpk_bitbase()[index] == KPK_WIN_STATE).then_some(strong)
+}
+
+// Results of KPK positions, as bits so they can be combined
+const KPK_INVALID: u8 = 0;
+const KPK_UNKNOWN: u8 = 1;
+const KPK_DRAW: u8 = 2;
+const KPK_WIN_STATE: u8 = 4;
+
+/// Side to move, the two kings and the pawn on files a to d and ranks 2 to 7
+const KPK_SIZE: usize = 2 * 64 * 64 * 24;
+
+/// Index of a KPK position with white to move or not, the pawn white and on the queenside
+fn kpk_index(white_to_move: bool, white_king: Square, black_king: Square, pawn: Square) -> usize {
+    let pawn = pawn.file() as usize + 4 * (pawn.rank() as usize - 1);
+    white_king.to_usize() | black_king.to_usize() << 6 | (white_to_move as usize) << 12 | pawn << 13
+}
+
+/// Results of all KPK positions, found by working back from positions with a known result
+fn kpk_bitbase() -> &'static [u8] {
+    static BITBASE: OnceLock<Vec<u8>> = OnceLock::new();
+    BITBASE.get_or_init(|| {
+        let positions: Vec<(bool, Square, Square, Square)> = (0..KPK_SIZE)
+            .map(|index| {
+                let pawn = index >> 13;
+                (
+                    index >> 12 & 1 == 1,
+                    Square::new((index & 63) as u32),
+                    Square::new((index >> 6 & 63) as u32),
+                    Square::from_coords(File::new(pawn as u32 % 4), Rank::new(pawn as u32 / 4 + 1)),
+                )
+            })
+            .collect();
+
+        let mut results: Vec<u8> = positions
+            .iter()
+            .map(|&(white_to_move, wk, bk, pawn)| kpk_initial(white_to_move, wk, bk, pawn))
+            .collect();
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (index, &position) in positions.iter().enumerate() {
+                if results[index] == KPK_UNKNOWN {
+                    let result = kpk_classify(&results, position);
+                    if result != KPK_UNKNOWN {
+                        results[index] = result;
+                        changed = true;
+                    }
+                }
+            }
+        }
+        results
+    })
+}
+
+/// Result of a position known without looking at its moves
+fn kpk_initial(white_to_move: bool, wk: Square, bk: Square, pawn: Square) -> u8 {
+    let promotion = pawn.offset(8);
+    if wk.distance(bk) <= 1
+        || wk == pawn
+        || bk == pawn
+        || (white_to_move && attacks::pawn_attacks(Color::White, pawn).contains(bk))
+    {
+        return KPK_INVALID;
+    }
+
+    // The pawn promotes without being taken
+    if let Some(promotion) = promotion.filter(|_| white_to_move && pawn.rank() == Rank::Seventh) {
+        if wk != promotion
+            && bk != promotion
+            && (bk.distance(promotion) > 1 || wk.distance(promotion) == 1)
+        {
+            return KPK_WIN_STATE;
+        }
+    }
+
+    if !white_to_move {
+        let guarded = attacks::king_attacks(wk) | attacks::pawn_attacks(Color::White, pawn);
+        // Stalemate, or the pawn is taken
+        if (attacks::king_attacks(bk) & !guarded).is_empty()
+            || (attacks::king_attacks(bk).contains(pawn)
+                && !attacks::king_attacks(wk).contains(pawn))
+        {
+            return KPK_DRAW;
+        }
+    }
+
+    KPK_UNKNOWN
+}
+
+/// Result of a position from the results after its moves, white wins if any move wins and
+/// black draws if any move draws
+fn kpk_classify(
+    results: &[u8],
+    (white_to_move, wk, bk, pawn): (bool, Square, Square, Square),
+) -> u8 {
+    let mut after = KPK_INVALID;
+    if white_to_move {
+        for to in attacks::king_attacks(wk) {
+            after |= results[kpk_index(false, to, bk, pawn)];
+        }
+        if pawn.rank() < Rank::Seventh {
+            let push = pawn.offset(8).unwrap();
+            after |= results[kpk_index(false, wk, bk, push)];
+            if pawn.rank() == Rank::Second && push != wk && push != bk {
+                after |= results[kpk_index(false, wk, bk, push.offset(8).unwrap())];
+            }
+        }
+    } else {
+        for to in attacks::king_attacks(bk) {
+            after |= results[kpk_index(true, wk, to, pawn)];
+        }
+    }
+
+    let (good, bad) = if white_to_move {
+        (KPK_WIN_STATE, KPK_DRAW)
+    } else {
+        (KPK_DRAW, KPK_WIN_STATE)
+    };
+    if after & good != 0 {
+        good
+    } else if after & KPK_UNKNOWN != 0 {
+        KPK_UNKNOWN
+    } else {
+        bad
+    }
+}
+
+/// Drive a lone enemy king to the edge and bring the own king closer, when there is enough
+/// material to mate
+fn mop_up(board: &Board, color: Color) -> Score {
+    if !is_lone_king(board, !color) || !(board.by_color(color) & board.pawns()).is_empty() {
+        return Score::default();
+    }
+    let (Some(king), Some(lone_king)) = (board.king_of(color), board.king_of(!color)) else {
+        return Score::default();
+    };
+
+    let ours = |role: Role| (board.by_role(role) & board.by_color(color)).count();
+    let bishops = board.by_role(Role::Bishop) & board.by_color(color);
+    let can_mate = ours(Role::Queen) > 0
+        || ours(Role::Rook) > 0
+        || (bishops.intersects(Bitboard::LIGHT_SQUARES)
+            && bishops.intersects(Bitboard::DARK_SQUARES))
+        || (ours(Role::Bishop) > 0 && ours(Role::Knight) > 0);
+    if !can_mate {
+        return Score::default();
+    }
+
+    // With bishop and knight only the corners of the bishop's color can be mated in
+    let edge = if pieces(board, color) == 2 && ours(Role::Bishop) == 1 && ours(Role::Knight) == 1 {
+        let corners = if bishops.intersects(Bitboard::DARK_SQUARES) {
+            [Square::A1, Square::H8]
+        } else {
+            [Square::A8, Square::H1]
+        };
+        let corner_distance = corners
+            .into_iter()
+            .map(|corner| lone_king.distance(corner))
+            .min()
+            .unwrap_or(0);
+        2 * (7 - corner_distance as i32)
+    } else {
+        center_distance(lone_king)
+    };
+
+    MOP_UP_EDGE * edge + MOP_UP_KINGS * (7 - king.distance(lone_king) as i32)
+}
+
+/// Manhattan distance from a square to the center, 0 in the center and 6 in a corner
+fn center_distance(sq: Square) -> i32 {
+    let file = sq.file() as i32;
+    let rank = sq.rank() as i32;
+    (3 - file).max(file - 4) + (3 - rank).max(rank - 4)
+}
+
+/// Bishop and pawns all on a rook file, whose promotion square the bishop doesn't cover, with
+/// the lone enemy king in front of them: a draw however many pawns
+fn is_wrong_bishop(board: &Board, color: Color) -> bool {
+    let ours = board.by_color(color);
+    let bishops = board.by_role(Role::Bishop) & ours;
+    let pawns = board.pawns() & ours;
+    if !is_lone_king(board, !color)
+        || pieces(board, color) != 1
+        || bishops.count() != 1
+        || pawns.is_empty()
+    {
+        return false;
+    }
+
+    let file = match pawns.first().map(Square::file) {
+        Some(File::A) => File::A,
+        Some(File::H) => File::H,
+        _ => return false,
+    };
+    if !(pawns & !Bitboard::from_file(file)).is_empty() {
+        return false;
+    }
+
+    let promotion = Square::from_coords(file, color.fold_wb(Rank::Eighth, Rank::First));
+    let bishop_is_light = bishops.intersects(Bitboard::LIGHT_SQUARES);
+    let Some(lone_king) = board.king_of(!color) else {
+        return false;
+    };
+    bishop_is_light != promotion.is_light() && lone_king.distance(promotion) <= 1
+}
+
+/// One bishop each, on squares of different colors, and nothing else but pawns
+fn is_opposite_bishops(board: &Board) -> bool {
+    let bishops = board.by_role(Role::Bishop);
+    Color::ALL
+        .into_iter()
+        .all(|color| pieces(board, color) == 1 && (bishops & board.by_color(color)).count() == 1)
+        && bishops.intersects(Bitboard::LIGHT_SQUARES)
+        && bishops.intersects(Bitboard::DARK_SQUARES)
+}
@@ -1,48 +1,417 @@
-use shakmaty::{ByRole, Chess, Position, Square};
+//! The handcrafted evaluation, broken down into terms by [`trace`].
+//!
+//! Material is counted in pawns (1/3/3/5/9), each piece in the center is worth 200 and each
+//! piece around it 100, and being in check costs 1000. Pawn structure, the pawn shield and
+//! mobility are there for `tune` to fill in, and skip their scans while weighted zero. Every term
+//! has a middlegame and an endgame value, blended by the game phase.
+
+use crate::search::endgame::{eval_endgame, scale_factor, SCALE_NORMAL};
+use crate::search::evaluator::{terminal_score, Evaluator};
+use crate::search::variant::{eval_variant, HasVariant};
+use shakmaty::{attacks, Bitboard, Board, ByColor, ByRole, Color, Role, Square};
+use std::fmt;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub};
+
+// Game phase weights, a position with all pieces on the board is at `MAX_PHASE`
+pub const MAX_PHASE: i32 = 24;
+const PHASE_WEIGHTS: ByRole<i32> = ByRole {
+    pawn: 0,
+    knight: 1,
+    bishop: 1,
+    rook: 2,
+    queen: 4,
+    king: 0,
+};
+
+/// Weights of the evaluation terms
+pub const DEFAULT_PARAMS: EvalParams = EvalParams {
+    // Material
+    piece_values: ByRole {
+        pawn: Score::new(1, 1),
+        knight: Score::new(3, 3),
+        bishop: Score::new(3, 3),
+        rook: Score::new(5, 5),
+        queen: Score::new(9, 9),
+        king: Score::new(0, 0),
+    },
+
+    // Piece placement
+    center: Score::new(200, 200),
+    around_center: Score::new(100, 100),
+
+    // Pawn structure
+    doubled_pawn: Score::new(0, 0),
+    isolated_pawn: Score::new(0, 0),
+    passed_pawn: [Score::new(0, 0); 8],
+
+    // King safety
+    pawn_shield: Score::new(0, 0),
+    in_check: Score::new(-1000, -1000),
+
+    // Mobility, per reachable square
+    mobility: ByRole {
+        pawn: Score::new(0, 0),
+        knight: Score::new(0, 0),
+        bishop: Score::new(0, 0),
+        rook: Score::new(0, 0),
+        queen: Score::new(0, 0),
+        king: Score::new(0, 0),
+    },
+};
+
+/// Tunable weights of the evaluation
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EvalParams {
+    pub piece_values: ByRole<Score>,
+    pub center: Score,
+    pub around_center: Score,
+    pub doubled_pawn: Score,
+    pub isolated_pawn: Score,
+    /// Indexed by the relative rank of the pawn
+    pub passed_pawn: [Score; 8],
+    pub pawn_shield: Score,
+    pub in_check: Score,
+    pub mobility: ByRole<Score>,
+}
+
+impl EvalParams {
+    /// All weights as a flat list, in declaration order
+    pub fn weights_mut(&mut self) -> Vec<&mut i32> {
+        let EvalParams {
+            piece_values,
+            center,
+            around_center,
+            doubled_pawn,
+            isolated_pawn,
+            passed_pawn,
+            pawn_shield,
+            in_check,
+            mobility,
+        } = self;
+
+        let mut scores: Vec<&mut Score> = Vec::new();
+        scores.extend(piece_values.iter_mut());
+        scores.extend([center, around_center, doubled_pawn, isolated_pawn]);
+        scores.extend(passed_pawn.iter_mut());
+        scores.extend([pawn_shield, in_check]);
+        scores.extend(mobility.iter_mut());
+
+        scores
+            .into_iter()
+            .flat_map(|score| [&mut score.mg, &mut score.eg])
+            .collect()
+    }
+}
+
+impl Default for EvalParams {
+    fn default() -> Self {
+        DEFAULT_PARAMS
+    }
+}
+
+/// Formats the parameters as Rust source, ready to replace `DEFAULT_PARAMS`
+impl fmt::Display for EvalParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn by_role(f: &mut fmt::Formatter<'_>, name: &str, values: &ByRole<Score>) -> fmt::Result {
+            writeln!(f, "    {name}: ByRole {{")?;
+            for (role, score) in Role::ALL.into_iter().zip(values.iter()) {
+                writeln!(
+                    f,
+                    "        {}: {},",
+                    format!("{role:?}").to_lowercase(),
+                    score
+                )?;
+            }
+            writeln!(f, "    }},")
+        }
+
+        writeln!(f, "pub const DEFAULT_PARAMS: EvalParams = EvalParams {{")?;
+        by_role(f, "piece_values", &self.piece_values)?;
+        writeln!(f, "    center: {},", self.center)?;
+        writeln!(f, "    around_center: {},", self.around_center)?;
+        writeln!(f, "    doubled_pawn: {},", self.doubled_pawn)?;
+        writeln!(f, "    isolated_pawn: {},", self.isolated_pawn)?;
+        writeln!(f, "    passed_pawn: [")?;
+        for score in &self.passed_pawn {
+            writeln!(f, "        {score},")?;
+        }
+        writeln!(f, "    ],")?;
+        writeln!(f, "    pawn_shield: {},", self.pawn_shield)?;
+        writeln!(f, "    in_check: {},", self.in_check)?;
+        by_role(f, "mobility", &self.mobility)?;
+        writeln!(f, "}};")
+    }
+}
 
 /// Evaluate the "value" of the position for the player who is about to move
-pub fn eval(position: &Chess) -> i32 {
-    let score = eval_material(position) + eval_position(position) + eval_checkstuff(position);
+pub fn eval<P: HasVariant>(position: &P) -> i32 {
+    eval_with(position, &DEFAULT_PARAMS)
+}
 
-    return score;
+/// Evaluate the position for the player who is about to move, using the given weights
+pub fn eval_with<P: HasVariant>(position: &P, params: &EvalParams) -> i32 {
+    if let Some(score) = terminal_score(position) {
+        return score;
+    }
+
+    // The same terms as `trace_with`, summed without keeping each one
+    let board = position.board();
+    let mut score = Score::default();
+    for color in Color::ALL {
+        let side = eval_material(board, color, params)
+            + eval_position(board, color, params)
+            + eval_pawns(board, color, params)
+            + eval_king_safety(position, color, params)
+            + eval_mobility(board, color, params)
+            + eval_variant(position, color, params)
+            + eval_endgame(position, color);
+        score += color.fold_wb(side, -side);
+    }
+    let score = score.taper(game_phase(board)) * scale_factor(position) / SCALE_NORMAL;
+
+    position.turn().fold_wb(score, -score)
 }
 
-fn eval_checkstuff(position: &Chess) -> i32 {
-    if position.is_checkmate() {
-        return i32::MIN + 1;
-    } else if position.is_check() {
-        return -1000;
+/// The handcrafted evaluation as an [`Evaluator`]
+#[derive(Clone, Debug, Default)]
+pub struct Handcrafted {
+    pub params: EvalParams,
+}
+
+impl<P: HasVariant> Evaluator<P> for Handcrafted {
+    fn evaluate(&mut self, position: &P) -> i32 {
+        eval_with(position, &self.params)
     }
-    return 0;
 }
 
-fn eval_position(position: &Chess) -> i32 {
-    let mut to_move_pieces_in_center = 0;
-    let mut to_move_pieces_around_center = 0;
+/// Break the static evaluation of the position down into its terms
+pub fn trace<P: HasVariant>(position: &P) -> Trace {
+    trace_with(position, &DEFAULT_PARAMS)
+}
 
-    let mut other_pieces_in_center = 0;
-    let mut other_pieces_around_center = 0;
+/// Break the static evaluation of the position down into its terms, using the given weights
+pub fn trace_with<P: HasVariant>(position: &P, params: &EvalParams) -> Trace {
+    let board = position.board();
+    let mut trace = Trace {
+        terms: Default::default(),
+        phase: game_phase(board),
+        scale: scale_factor(position),
+    };
 
-    for (sq, pc) in position.board() {
-        if is_around_center(sq) {
-            if pc.color == position.turn() {
-                to_move_pieces_around_center += 1;
-            } else {
-                other_pieces_around_center += 1;
-            }
+    for color in Color::ALL {
+        trace.set(Term::Material, color, eval_material(board, color, params));
+        trace.set(Term::Pst, color, eval_position(board, color, params));
+        trace.set(Term::Pawns, color, eval_pawns(board, color, params));
+        trace.set(
+            Term::KingSafety,
+            color,
+            eval_king_safety(position, color, params),
+        );
+        trace.set(Term::Mobility, color, eval_mobility(board, color, params));
+        trace.set(Term::Variant, color, eval_variant(position, color, params));
+        trace.set(Term::Endgame, color, eval_endgame(position, color));
+    }
+
+    trace
+}
+
+/// The terms the evaluation is made up of
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Term {
+    Material,
+    Pst,
+    Pawns,
+    KingSafety,
+    Mobility,
+    Variant,
+    Endgame,
+}
+
+impl Term {
+    pub const ALL: [Term; 7] = [
+        Term::Material,
+        Term::Pst,
+        Term::Pawns,
+        Term::KingSafety,
+        Term::Mobility,
+        Term::Variant,
+        Term::Endgame,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Term::Material => "Material",
+            Term::Pst => "PST",
+            Term::Pawns => "Pawns",
+            Term::KingSafety => "King safety",
+            Term::Mobility => "Mobility",
+            Term::Variant => "Variant",
+            Term::Endgame => "Endgame",
+        }
+    }
+}
+
+/// A score with separate middlegame and endgame values
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Score {
+    pub mg: i32,
+    pub eg: i32,
+}
+
+impl Score {
+    pub const fn new(mg: i32, eg: i32) -> Self {
+        Score { mg, eg }
+    }
+
+    /// Interpolate between the middlegame and endgame values
+    pub fn taper(self, phase: i32) -> i32 {
+        (self.mg * phase + self.eg * (MAX_PHASE - phase)) / MAX_PHASE
+    }
+}
+
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Score::new({}, {})", self.mg, self.eg)
+    }
+}
+
+impl Add for Score {
+    type Output = Score;
+
+    fn add(self, other: Score) -> Score {
+        Score::new(self.mg + other.mg, self.eg + other.eg)
+    }
+}
+
+impl AddAssign for Score {
+    fn add_assign(&mut self, other: Score) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Score {
+    type Output = Score;
+
+    fn sub(self, other: Score) -> Score {
+        Score::new(self.mg - other.mg, self.eg - other.eg)
+    }
+}
+
+impl Neg for Score {
+    type Output = Score;
+
+    fn neg(self) -> Score {
+        Score::new(-self.mg, -self.eg)
+    }
+}
+
+impl Mul<i32> for Score {
+    type Output = Score;
+
+    fn mul(self, factor: i32) -> Score {
+        Score::new(self.mg * factor, self.eg * factor)
+    }
+}
+
+/// Per-term, per-side breakdown of the static evaluation
+pub struct Trace {
+    pub terms: [ByColor<Score>; Term::ALL.len()],
+    /// Game phase, from 0 (endgame) to 24 (middlegame)
+    pub phase: i32,
+    /// Factor the total is scaled by, out of `SCALE_NORMAL`, lower in endings hard to win
+    pub scale: i32,
+}
+
+impl Trace {
+    /// Score of a single term for one side
+    pub fn get(&self, term: Term, color: Color) -> Score {
+        *self.terms[term as usize].get(color)
+    }
+
+    fn set(&mut self, term: Term, color: Color, score: Score) {
+        *self.terms[term as usize].get_mut(color) = score;
+    }
+
+    /// Sum of all terms from white's point of view, before tapering
+    pub fn score(&self) -> Score {
+        let mut score = Score::default();
+        for term in Term::ALL {
+            score += self.get(term, Color::White) - self.get(term, Color::Black);
         }
+        score
+    }
 
+    /// Tapered and scaled evaluation from white's point of view
+    pub fn total(&self) -> i32 {
+        self.score().taper(self.phase) * self.scale / SCALE_NORMAL
+    }
+}
+
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "         Term |    White    |    Black    |    Total")?;
+        writeln!(f, "              |   MG    EG  |   MG    EG  |   MG    EG")?;
+        writeln!(f, " -------------+-------------+-------------+------------")?;
+        for term in Term::ALL {
+            let white = self.get(term, Color::White);
+            let black = self.get(term, Color::Black);
+            let total = white - black;
+            writeln!(
+                f,
+                " {:>12} | {:>5} {:>5} | {:>5} {:>5} | {:>5} {:>5}",
+                term.name(),
+                white.mg,
+                white.eg,
+                black.mg,
+                black.eg,
+                total.mg,
+                total.eg
+            )?;
+        }
+        let score = self.score();
+        writeln!(f, " -------------+-------------+-------------+------------")?;
+        writeln!(
+            f,
+            " {:>12} |             |             | {:>5} {:>5}",
+            "Total", score.mg, score.eg
+        )?;
+        writeln!(f)?;
+        writeln!(f, "Phase: {}/{}", self.phase, MAX_PHASE)?;
+        writeln!(f, "Scale: {}/{}", self.scale, SCALE_NORMAL)?;
+        write!(f, "Final evaluation: {} (white side)", self.total())
+    }
+}
+
+fn game_phase(board: &Board) -> i32 {
+    let mut phase = 0;
+    for role in Role::ALL {
+        phase += board.by_role(role).count() as i32 * PHASE_WEIGHTS.get(role);
+    }
+    phase.min(MAX_PHASE)
+}
+
+fn eval_material(board: &Board, color: Color, params: &EvalParams) -> Score {
+    let material = board.material_side(color);
+
+    let mut score = Score::default();
+    for role in Role::ALL {
+        score += *params.piece_values.get(role) * *material.get(role) as i32;
+    }
+    score
+}
+
+fn eval_position(board: &Board, color: Color, params: &EvalParams) -> Score {
+    let mut score = Score::default();
+
+    for sq in board.by_color(color) {
         if is_center(sq) {
-            if pc.color == position.turn() {
-                to_move_pieces_in_center += 1;
-            } else {
-                other_pieces_in_center += 1;
-            }
+            score += params.center;
+        } else if is_around_center(sq) {
+            score += params.around_center;
         }
     }
 
-    return (to_move_pieces_in_center - other_pieces_in_center) * 200
-        + (to_move_pieces_around_center - other_pieces_around_center) * 100;
+    score
 }
 
 fn is_around_center(sq: Square) -> bool {
@@ -60,25 +429,122 @@ fn is_center(sq: Square) -> bool {
         && sq.rank().le(&shakmaty::Rank::Fifth)
 }
 
-fn eval_material(position: &Chess) -> i32 {
-    let material = position.board().material();
-    let to_move_material_score = material_score(material.get(position.turn()));
-    let other_material_score = material_score(material.get(position.turn().other()));
+fn eval_pawns(board: &Board, color: Color, params: &EvalParams) -> Score {
+    let weights = [&params.doubled_pawn, &params.isolated_pawn];
+    if is_unweighted(weights.into_iter().chain(&params.passed_pawn)) {
+        return Score::default();
+    }
+    let ours = board.by_piece(color.pawn());
+    let theirs = board.by_piece(color.other().pawn());
+
+    let mut score = Score::default();
 
-    let diff = to_move_material_score as i32 - other_material_score as i32;
+    for sq in ours {
+        let file = Bitboard::from_file(sq.file());
+        let adjacent = adjacent_files(sq);
 
-    return diff;
+        if (ours & file).more_than_one() {
+            score += params.doubled_pawn;
+        }
+        if (ours & adjacent).is_empty() {
+            score += params.isolated_pawn;
+        }
+        if (theirs & front_span(color, sq)).is_empty()
+            && (ours & file & ahead(color, sq)).is_empty()
+        {
+            score += params.passed_pawn[color.relative_rank(sq.rank()) as usize];
+        }
+    }
+
+    score
+}
+
+/// Squares on the neighbouring files of a square
+fn adjacent_files(sq: Square) -> Bitboard {
+    let mut files = Bitboard::EMPTY;
+    for delta in [-1, 1] {
+        if let Some(file) = sq.file().offset(delta) {
+            files |= Bitboard::from_file(file);
+        }
+    }
+    files
+}
+
+/// Squares strictly in front of a square, from `color`'s point of view
+fn ahead(color: Color, sq: Square) -> Bitboard {
+    let mut squares = Bitboard::EMPTY;
+    let mut rank = sq.rank();
+    while let Some(next) = rank.offset(color.fold_wb(1, -1)) {
+        squares |= Bitboard::from_rank(next);
+        rank = next;
+    }
+    squares
+}
+
+/// Squares an enemy pawn would have to be on to stop a pawn from promoting
+fn front_span(color: Color, sq: Square) -> Bitboard {
+    ahead(color, sq) & (adjacent_files(sq) | Bitboard::from_file(sq.file()))
+}
+
+fn eval_king_safety<P: HasVariant>(position: &P, color: Color, params: &EvalParams) -> Score {
+    let board = position.board();
+    let Some(king) = board.king_of(color) else {
+        return Score::default();
+    };
+
+    let mut score = Score::default();
+
+    if position.turn() == color && position.is_check() {
+        score += params.in_check;
+    }
+    if is_unweighted([&params.pawn_shield]) {
+        return score;
+    }
+
+    // Own pawns on the two ranks in front of the king
+    let shield_files = adjacent_files(king) | Bitboard::from_file(king.file());
+    let mut shield_ranks = Bitboard::EMPTY;
+    let mut rank = king.rank();
+    for _ in 0..2 {
+        match rank.offset(color.fold_wb(1, -1)) {
+            Some(next) => {
+                shield_ranks |= Bitboard::from_rank(next);
+                rank = next;
+            }
+            None => break,
+        }
+    }
+    let shield = board.by_piece(color.pawn()) & shield_files & shield_ranks;
+    score += params.pawn_shield * shield.count() as i32;
+
+    score
 }
 
-fn material_score(material: &ByRole<u8>) -> u8 {
-    let ByRole {
-        pawn,
-        knight,
-        bishop,
-        rook,
-        queen,
-        king,
-    } = material;
+fn eval_mobility(board: &Board, color: Color, params: &EvalParams) -> Score {
+    if is_unweighted(params.mobility.iter()) {
+        return Score::default();
+    }
+    let occupied = board.occupied();
+    let ours = board.by_color(color);
+
+    let mut score = Score::default();
+
+    for role in [Role::Knight, Role::Bishop, Role::Rook, Role::Queen] {
+        for sq in board.by_piece(role.of(color)) {
+            let reachable = match role {
+                Role::Knight => attacks::knight_attacks(sq),
+                Role::Bishop => attacks::bishop_attacks(sq, occupied),
+                Role::Rook => attacks::rook_attacks(sq, occupied),
+                _ => attacks::queen_attacks(sq, occupied),
+            };
+            score += *params.mobility.get(role) * (reachable & !ours).count() as i32;
+        }
+    }
+
+    score
+}
 
-    return pawn + knight * 3 + bishop * 3 + rook * 5 + queen * 9 + king * 100;
+/// Whether a term scores nothing with these weights, so it needn't look at the board
+fn is_unweighted<'a>(weights: impl IntoIterator<Item = &'a Score>) -> bool {
+    weights.into_iter().all(|&score| score == Score::default())
 }
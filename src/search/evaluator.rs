@@ -0,0 +1,38 @@
+use shakmaty::variant::VariantPosition;
+use shakmaty::{KnownOutcome, Move, Outcome, Position};
+
+/// Score of a won game, for the player who is about to move
+pub const WIN_SCORE: i32 = i32::MAX;
+
+/// Static evaluation used by the search.
+///
+/// The search reports every move it makes and takes back, so evaluators can keep incremental
+/// state (accumulators, caches) in sync with the position being evaluated.
+pub trait Evaluator<P: Position = VariantPosition>: Send {
+    /// Prepare for a search from `position`
+    fn reset(&mut self, _position: &P) {}
+
+    /// Called before `mv` is played in `position`
+    fn make_move(&mut self, _position: &P, _mv: Move) {}
+
+    /// Called after the last made move is taken back
+    fn unmake_move(&mut self) {}
+
+    /// Evaluate the "value" of the position for the player who is about to move
+    fn evaluate(&mut self, position: &P) -> i32;
+}
+
+/// Score of a finished game for the player who is about to move, `None` if it is not over.
+///
+/// Follows the rules of the variant, so this covers e.g. a king reaching the center in King of
+/// the Hill or losing all pieces in Antichess.
+pub fn terminal_score<P: Position>(position: &P) -> Option<i32> {
+    match position.outcome() {
+        Outcome::Known(KnownOutcome::Decisive { winner }) if winner == position.turn() => {
+            Some(WIN_SCORE)
+        }
+        Outcome::Known(KnownOutcome::Decisive { .. }) => Some(-WIN_SCORE),
+        Outcome::Known(KnownOutcome::Draw) => Some(0),
+        Outcome::Unknown => None,
+    }
+}
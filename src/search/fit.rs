@@ -0,0 +1,77 @@
+//! Fitting to game results, shared by the `tune` and `wdl` tools: reading labeled positions and
+//! the optimizer that takes the steps.
+
+use shakmaty::fen::Fen;
+use shakmaty::{CastlingMode, Chess};
+use std::str::FromStr;
+
+// Parameters
+const BETA1: f64 = 0.9;
+const BETA2: f64 = 0.999;
+const EPSILON: f64 = 1e-8;
+
+/// Splits a line into a position and a game result from white's point of view.
+///
+/// The line holds a FEN, with or without the move counters, and the result as `1-0`, `0-1`,
+/// `1/2-1/2` or `[1.0]`, `[0.0]`, `[0.5]`.
+pub fn parse_line(line: &str) -> Option<(Chess, f64)> {
+    let result = if line.contains("1/2-1/2") || line.contains("[0.5]") {
+        0.5
+    } else if line.contains("1-0") || line.contains("[1.0]") {
+        1.0
+    } else if line.contains("0-1") || line.contains("[0.0]") {
+        0.0
+    } else {
+        return None;
+    };
+
+    // Board, turn, castling and en passant, optionally followed by the move counters
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    if tokens.len() < 4 {
+        return None;
+    }
+    let counters = tokens[4..]
+        .iter()
+        .take(2)
+        .take_while(|token| token.chars().all(|c| c.is_ascii_digit()))
+        .count();
+    let fen = &tokens[..4 + counters];
+
+    let position = Fen::from_str(&fen.join(" "))
+        .ok()?
+        .into_position(CastlingMode::Standard)
+        .ok()?;
+
+    Some((position, result))
+}
+
+/// Adam optimizer, keeping the moving averages of the gradient between steps
+pub struct Adam {
+    learning_rate: f64,
+    steps: i32,
+    m: Vec<f64>,
+    v: Vec<f64>,
+}
+
+impl Adam {
+    pub fn new(len: usize, learning_rate: f64) -> Self {
+        Adam {
+            learning_rate,
+            steps: 0,
+            m: vec![0.0; len],
+            v: vec![0.0; len],
+        }
+    }
+
+    /// Move `params` down the `gradient`
+    pub fn step(&mut self, params: &mut [f64], gradient: &[f64]) {
+        self.steps += 1;
+        for i in 0..params.len() {
+            self.m[i] = BETA1 * self.m[i] + (1.0 - BETA1) * gradient[i];
+            self.v[i] = BETA2 * self.v[i] + (1.0 - BETA2) * gradient[i] * gradient[i];
+            let m_hat = self.m[i] / (1.0 - BETA1.powi(self.steps));
+            let v_hat = self.v[i] / (1.0 - BETA2.powi(self.steps));
+            params[i] -= self.learning_rate * m_hat / (v_hat.sqrt() + EPSILON);
+        }
+    }
+}
@@ -0,0 +1,294 @@
+//! Monte Carlo tree search with PUCT selection, using the static evaluation as value function.
+
+use crate::search::algorithm::{Limits, SearchAlgorithm, SearchContext};
+use crate::search::evaluator::{terminal_score, Evaluator};
+use crate::SearchInfo;
+use shakmaty::variant::VariantPosition;
+use shakmaty::{Move, Position};
+
+// Parameters
+const EXPLORATION: f32 = 1.5;
+/// Evaluation (in centipawns) that maps to a value of about 0.76
+const VALUE_SCALE: f32 = 400.0;
+/// Iterations between info reports
+const REPORT_INTERVAL: u64 = 2000;
+/// Iterations before giving up on reaching the requested depth
+const MAX_ITERATIONS: u64 = 1_000_000;
+/// Deepest path a single iteration may walk down the tree
+const MAX_PLY: usize = 128;
+/// Visits a node needs, beyond the first move, to be part of the principal variation
+const PV_MIN_VISITS: u32 = 50;
+
+struct Node {
+    /// Move leading to this node, `None` for the root
+    mv: Option<Move>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    prior: f32,
+    visits: u32,
+    /// Sum of values from the point of view of the player who made `mv`
+    value_sum: f32,
+    expanded: bool,
+}
+
+impl Node {
+    fn new(mv: Option<Move>, parent: Option<usize>, prior: f32) -> Self {
+        Node {
+            mv,
+            parent,
+            children: Vec::new(),
+            prior,
+            visits: 0,
+            value_sum: 0.0,
+            expanded: false,
+        }
+    }
+
+    /// Average value from the point of view of the player who made `mv`
+    fn q(&self) -> f32 {
+        if self.visits == 0 {
+            0.0
+        } else {
+            self.value_sum / self.visits as f32
+        }
+    }
+
+    /// Expanded without children: checkmate or a draw
+    fn is_terminal(&self) -> bool {
+        self.expanded && self.children.is_empty()
+    }
+}
+
+/// Monte Carlo tree search
+pub struct Mcts;
+
+impl SearchAlgorithm for Mcts {
+    fn search(
+        &mut self,
+        position: &VariantPosition,
+        limits: &Limits,
+        ctx: &mut SearchContext,
+    ) -> Move {
+        let skill = ctx.options.skill;
+        let (max_depth, max_iterations) = if skill.is_full_strength() {
+            (limits.depth, MAX_ITERATIONS)
+        } else {
+            let depth = limits
+                .depth
+                .map_or(skill.depth(), |depth| depth.min(skill.depth()));
+            (Some(depth), skill.nodes().min(MAX_ITERATIONS))
+        };
+        let mut tree = vec![Node::new(None, None, 1.0)];
+        let mut iterations = 0;
+        let mut seldepth = 0;
+
+        loop {
+            seldepth = seldepth.max(iterate(&mut tree, position, ctx.evaluator));
+            iterations += 1;
+
+            let pv = principal_variation(&tree);
+            let reached_depth = max_depth.is_some_and(|depth| pv.len() >= depth as usize);
+            // Only a move that wins on the spot settles the search, a draw or a mate further
+            // down the line may still be avoided
+            let solved = pv
+                .first()
+                .is_some_and(|&node| tree[node].is_terminal() && tree[node].q() > 0.0);
+
+            let done = limits.time_up()
+                || limits.stopped()
+                || reached_depth
+                || solved
+                || iterations >= max_iterations
+                || tree[0].children.is_empty();
+
+            if done || iterations % REPORT_INTERVAL == 0 {
+                send_info(&tree, &pv, iterations, seldepth, limits, ctx);
+            }
+            if done {
+                break;
+            }
+        }
+
+        let Some(&best) = principal_variation(&tree).first() else {
+            // Fall back to any legal move if the root could not be expanded
+            return position.legal_moves()[0];
+        };
+        if skill.is_full_strength() {
+            return tree[best].mv.unwrap();
+        }
+
+        // A weakened engine chooses among the most visited moves
+        let mut children: Vec<usize> = tree[0]
+            .children
+            .iter()
+            .copied()
+            .filter(|&child| child != best && tree[child].visits > 0)
+            .collect();
+        children.sort_by_key(|&child| std::cmp::Reverse(tree[child].visits));
+        let scored_moves: Vec<(Move, i32)> = std::iter::once(best)
+            .chain(children)
+            .map(|child| (tree[child].mv.unwrap(), centipawns(tree[child].q())))
+            .collect();
+        skill.pick(&scored_moves)
+    }
+}
+
+/// Select a leaf, expand and evaluate it, and back the value up to the root. Returns the depth
+/// of the leaf.
+fn iterate<P: Position + Clone>(
+    tree: &mut Vec<Node>,
+    root: &P,
+    evaluator: &mut dyn Evaluator<P>,
+) -> usize {
+    let mut node = 0;
+    let mut position = root.clone();
+    let mut ply = 0;
+
+    // Selection
+    while tree[node].expanded && !tree[node].children.is_empty() && ply < MAX_PLY {
+        node = select_child(tree, node);
+        let mv = tree[node].mv.unwrap();
+        evaluator.make_move(&position, mv);
+        position.play_unchecked(mv);
+        ply += 1;
+    }
+
+    // Expansion and evaluation, from the point of view of the player to move at `node`
+    let value = if let Some(score) = terminal_score(&position) {
+        // Won, lost or drawn under the rules of the variant
+        tree[node].expanded = true;
+        score.signum() as f32
+    } else {
+        if !tree[node].expanded {
+            expand(tree, node, &position);
+        }
+        (evaluator.evaluate(&position) as f32 / VALUE_SCALE).tanh()
+    };
+
+    for _ in 0..ply {
+        evaluator.unmake_move();
+    }
+
+    // Backpropagation
+    let mut value = value;
+    let mut current = Some(node);
+    while let Some(index) = current {
+        tree[index].visits += 1;
+        tree[index].value_sum -= value;
+        value = -value;
+        current = tree[index].parent;
+    }
+
+    ply
+}
+
+/// Child maximizing the PUCT score.
+///
+/// Children are all tried once first, otherwise a winning first move keeps the others from being
+/// looked at, which can hide a mate in one behind it.
+fn select_child(tree: &[Node], parent: usize) -> usize {
+    let sqrt_visits = (tree[parent].visits as f32).sqrt();
+
+    let puct = |child: usize| {
+        let node = &tree[child];
+        if node.visits == 0 {
+            return f32::INFINITY;
+        }
+        node.q() + EXPLORATION * node.prior * sqrt_visits / (1.0 + node.visits as f32)
+    };
+
+    let mut best = tree[parent].children[0];
+    let mut best_score = f32::NEG_INFINITY;
+    for &child in &tree[parent].children {
+        let score = puct(child);
+        if score > best_score {
+            best_score = score;
+            best = child;
+        }
+    }
+    best
+}
+
+/// Add a child for every legal move, with uniform priors
+fn expand<P: Position>(tree: &mut Vec<Node>, node: usize, position: &P) {
+    let moves = position.legal_moves();
+    let prior = 1.0 / moves.len() as f32;
+
+    for mv in moves {
+        let child = tree.len();
+        tree.push(Node::new(Some(mv), Some(node), prior));
+        tree[node].children.push(child);
+    }
+    tree[node].expanded = true;
+}
+
+/// Most visited path from the root, excluding the root itself.
+///
+/// The path ends when a node has been visited too little to be trusted, which also makes its
+/// length a measure of how deep the search has looked.
+fn principal_variation(tree: &[Node]) -> Vec<usize> {
+    let mut pv = Vec::new();
+    let mut node = 0;
+
+    while let Some(&child) = tree[node]
+        .children
+        .iter()
+        .max_by_key(|&&child| tree[child].visits)
+        .filter(|&&child| {
+            tree[child].visits >= PV_MIN_VISITS || (pv.is_empty() && tree[child].visits > 0)
+        })
+    {
+        pv.push(child);
+        node = child;
+    }
+
+    pv
+}
+
+/// Report the principal variation, and the most visited alternatives to its first move
+fn send_info(
+    tree: &[Node],
+    pv: &[usize],
+    iterations: u64,
+    seldepth: usize,
+    limits: &Limits,
+    ctx: &SearchContext,
+) {
+    let Some(&best) = pv.first() else {
+        return;
+    };
+    let depth = pv.len().min(u8::MAX as usize) as u8;
+    let seldepth = seldepth.clamp(depth as usize, u8::MAX as usize) as u8;
+
+    let mut alternatives: Vec<usize> = tree[0]
+        .children
+        .iter()
+        .copied()
+        .filter(|&child| child != best && tree[child].visits > 0)
+        .collect();
+    alternatives.sort_by_key(|&child| std::cmp::Reverse(tree[child].visits));
+
+    let lines = std::iter::once(pv.to_vec())
+        .chain(alternatives.into_iter().map(|child| vec![child]))
+        .take(ctx.options.multi_pv as usize);
+
+    for (i, line) in lines.enumerate() {
+        let _ = ctx.info_tx.send(SearchInfo::Info {
+            depth,
+            seldepth,
+            multipv: i as u8 + 1,
+            pv: line.iter().map(|&node| tree[node].mv.unwrap()).collect(),
+            score: centipawns(tree[line[0]].q()),
+            nodes: iterations,
+            time: limits.start.elapsed(),
+            // The tree takes the place of the transposition table
+            hashfull: None,
+            tbhits: 0,
+        });
+    }
+}
+
+/// Convert a value back to centipawns
+fn centipawns(q: f32) -> i32 {
+    (q.clamp(-0.999, 0.999).atanh() * VALUE_SCALE) as i32
+}
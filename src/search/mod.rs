@@ -1,101 +1,229 @@
-mod eval;
-mod negamax;
-
-use std::i32;
+mod algorithm;
+pub mod bench;
+pub mod endgame;
+pub mod eval;
+mod evaluator;
+pub mod fit;
+pub mod mcts;
+pub mod negamax;
+pub mod nnue;
+pub mod perft;
+pub mod skill;
+pub mod tablebase;
+pub mod tt;
+pub mod tune;
+pub mod variant;
+pub mod wdl;
+
+pub use algorithm::{Limits, SearchAlgorithm, SearchContext};
+pub use evaluator::Evaluator;
+
+use std::sync::atomic::Ordering;
 use std::time::{Duration, Instant};
 
-use crate::search::negamax::{negamax, Report};
+use crate::search::negamax::Negamax;
+use crate::search::skill::Skill;
+use crate::search::tablebase::Tablebases;
+use crate::search::tt::TranspositionTable;
 use crate::{SearchCommand, SearchControl, SearchInfo};
-use crossbeam_channel::{Receiver, Sender};
-use shakmaty::{Chess, Move, Position};
+use crossbeam_channel::{bounded, select, Receiver, Sender};
+use shakmaty::variant::VariantPosition;
+use shakmaty::{Move, Position};
+
+/// Settings of the search, changed through UCI options
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchOptions {
+    /// Size of the transposition table in megabytes
+    pub hash_mb: usize,
+    /// Number of best lines to report
+    pub multi_pv: u8,
+    /// How well to play, set by `Skill Level` or `UCI_Elo`
+    pub skill: Skill,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        SearchOptions {
+            hash_mb: 16,
+            multi_pv: 1,
+            skill: Skill::default(),
+        }
+    }
+}
+
+/// Everything kept from one search to the next: evaluation, algorithm, settings, hash table and
+/// endgame tablebases
+pub struct SearchState {
+    evaluator: Box<dyn Evaluator>,
+    algorithm: Box<dyn SearchAlgorithm>,
+    options: SearchOptions,
+    tt: TranspositionTable,
+    tablebases: Option<Tablebases>,
+}
+
+impl SearchState {
+    pub fn new(evaluator: Box<dyn Evaluator>) -> Self {
+        let options = SearchOptions::default();
+        SearchState {
+            evaluator,
+            algorithm: Box::new(Negamax),
+            tt: TranspositionTable::new(options.hash_mb),
+            options,
+            tablebases: None,
+        }
+    }
+
+    /// Scores stored by the previous evaluator, or for another variant, are forgotten
+    pub fn set_evaluator(&mut self, evaluator: Box<dyn Evaluator>) {
+        self.evaluator = evaluator;
+        self.tt.clear();
+    }
+
+    pub fn set_algorithm(&mut self, algorithm: Box<dyn SearchAlgorithm>) {
+        self.algorithm = algorithm;
+    }
+
+    pub fn set_options(&mut self, options: SearchOptions) {
+        if options.hash_mb != self.options.hash_mb {
+            self.tt = TranspositionTable::new(options.hash_mb);
+        }
+        self.options = options;
+    }
+
+    pub fn clear_hash(&mut self) {
+        self.tt.clear();
+    }
+
+    pub fn set_tablebases(&mut self, tablebases: Option<Tablebases>) {
+        self.tablebases = tablebases;
+    }
+
+    /// Search `position` within `limits`, reporting progress on `info_tx`, and return the best move,
+    /// `None` if there are no legal moves
+    pub fn search(
+        &mut self,
+        position: &VariantPosition,
+        limits: &Limits,
+        info_tx: &Sender<SearchInfo>,
+    ) -> Option<Move> {
+        if position.legal_moves().is_empty() {
+            return None;
+        }
+        self.evaluator.reset(position);
+
+        let mut ctx = SearchContext {
+            evaluator: self.evaluator.as_mut(),
+            tt: &mut self.tt,
+            options: &self.options,
+            // The tables are for standard chess only
+            tablebases: self
+                .tablebases
+                .as_ref()
+                .filter(|_| matches!(position, VariantPosition::Chess(_))),
+            info_tx,
+        };
+        Some(self.algorithm.search(position, limits, &mut ctx))
+    }
+}
 
 /// Executes search tasks.
 pub struct Searcher {
     cmd_rx: Receiver<SearchCommand>,
     info_tx: Sender<SearchInfo>,
+    state: SearchState,
 }
 
 impl Searcher {
-    pub fn new(cmd_rx: Receiver<SearchCommand>, info_tx: Sender<SearchInfo>) -> Self {
-        Searcher { cmd_rx, info_tx }
+    pub fn new(
+        cmd_rx: Receiver<SearchCommand>,
+        info_tx: Sender<SearchInfo>,
+        evaluator: Box<dyn Evaluator>,
+    ) -> Self {
+        Searcher {
+            cmd_rx,
+            info_tx,
+            state: SearchState::new(evaluator),
+        }
     }
 
     /// Run the searcher
     pub fn run(mut self) {
-        loop {
-            match self.cmd_rx.recv() {
-                Ok(SearchCommand::Start { position, control }) => self.search(position, control),
-                Ok(SearchCommand::Stop) => (),
-                Ok(SearchCommand::Quit) | Err(_) => break,
+        while let Ok(cmd) = self.cmd_rx.recv() {
+            if !self.handle(cmd) {
+                break;
             }
         }
     }
 
-    fn search(&mut self, position: Chess, control: SearchControl) {
-        // Determine search constraints
-        let (_max_depth, _time_limit) = match control {
-            SearchControl::ToDepth(depth) => (depth, u64::MAX),
-            SearchControl::TimeLimit(time_limit) => (u8::MAX, time_limit),
-        };
-
-        let max_duration = std::time::Duration::from_millis(_time_limit);
-
-        let start = std::time::Instant::now();
-
-        let mut search_depth = 1;
-        let mut best_move = self.find_best_move(position.clone(), search_depth);
-        while search_depth < 4 && Instant::now().duration_since(start).lt(&max_duration) {
-            search_depth += 1;
-            best_move = self.find_best_move(position.clone(), search_depth);
-        }
-
-        if (max_duration - Instant::now().duration_since(start)).gt(&Duration::from_millis(500)) {
-            std::thread::sleep(Duration::from_millis(400));
+    /// Carry out a command, returns `false` once the searcher should quit
+    fn handle(&mut self, cmd: SearchCommand) -> bool {
+        match cmd {
+            SearchCommand::Start { position, control } => return self.search(position, control),
+            SearchCommand::SetEvaluator(evaluator) => self.state.set_evaluator(evaluator),
+            SearchCommand::SetAlgorithm(algorithm) => self.state.set_algorithm(algorithm),
+            SearchCommand::SetOptions(options) => self.state.set_options(options),
+            SearchCommand::ClearHash => self.state.clear_hash(),
+            SearchCommand::SetTablebases(tablebases) => self.state.set_tablebases(tablebases),
+            SearchCommand::Stop => (),
+            SearchCommand::Quit => return false,
         }
-
-        // It is necessary to send info at least once to En Croissant (the user interface) before outputting best move.
-
-        // Output best move
-        self.info_tx.send(SearchInfo::BestMove(best_move)).unwrap();
+        true
     }
 
-    fn find_best_move(&self, position: Chess, search_depth: u8) -> Move {
-        let mut report = Report { nodes_visited: 0 };
-        let mut max_score = i32::MIN;
-        let mut best_move: Move = position.legal_moves()[0].clone();
-
-        for mv in position.legal_moves() {
-            let result_position = position.clone().play(mv).unwrap();
-            let score = -negamax(
-                result_position,
-                search_depth - 1,
-                crate::search::eval::eval,
-                &mut report,
-            );
-
-            if score > max_score {
-                max_score = score;
-                best_move = mv;
+    /// Search while still taking commands: `stop` ends the search early, and the rest are
+    /// carried out once it is over. Returns `false` once the searcher should quit.
+    fn search(&mut self, position: VariantPosition, control: SearchControl) -> bool {
+        // Determine search constraints
+        let limits = Limits::new(&control);
+        let mut pending = Vec::new();
+        let mut quit = false;
+
+        let best_move = std::thread::scope(|scope| {
+            let (done_tx, done_rx) = bounded(1);
+            let state = &mut self.state;
+            let info_tx = &self.info_tx;
+            let limits = &limits;
+            let search = scope.spawn(move || {
+                let best_move = state.search(&position, limits, info_tx);
+
+                // It is necessary to send info at least once to En Croissant (the user interface) before outputting best move.
+                let remaining = limits
+                    .time
+                    .unwrap_or(Duration::MAX)
+                    .saturating_sub(limits.start.elapsed());
+                let paused = Instant::now();
+                while remaining > Duration::from_millis(500)
+                    && paused.elapsed() < Duration::from_millis(400)
+                    && !limits.stopped()
+                {
+                    std::thread::sleep(Duration::from_millis(10));
+                }
+
+                done_tx.send(()).unwrap();
+                best_move
+            });
+
+            while !quit {
+                select! {
+                    recv(self.cmd_rx) -> cmd => match cmd {
+                        Ok(SearchCommand::Stop) => limits.stop.store(true, Ordering::Relaxed),
+                        Ok(SearchCommand::Quit) | Err(_) => {
+                            limits.stop.store(true, Ordering::Relaxed);
+                            quit = true;
+                        }
+                        Ok(cmd) => pending.push(cmd),
+                    },
+                    recv(done_rx) -> _ => break,
+                }
             }
+            search.join().unwrap()
+        });
+
+        // Output best move, unless the controller has gone and takes no more output
+        if !quit {
+            let _ = self.info_tx.send(SearchInfo::BestMove(best_move));
         }
-        self.send_info(
-            search_depth,
-            vec![best_move],
-            max_score,
-            report.nodes_visited,
-        );
-
-        return best_move;
-    }
 
-    fn send_info(&self, depth: u8, pv: Vec<Move>, score: i32, nodes: u64) {
-        self.info_tx
-            .send(SearchInfo::Info {
-                depth,
-                pv,
-                score,
-                nodes,
-            })
-            .unwrap();
+        !quit && pending.into_iter().all(|cmd| self.handle(cmd))
     }
 }
@@ -1,28 +1,285 @@
 use std::i32;
 
-use shakmaty::{Chess, Move, Position as _};
+use crate::search::algorithm::{Limits, SearchAlgorithm, SearchContext};
+use crate::search::evaluator::Evaluator;
+use crate::search::tablebase::Tablebases;
+use crate::search::tt::TranspositionTable;
+use crate::SearchInfo;
+use shakmaty::variant::VariantPosition;
+use shakmaty::{Move, Position};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Deepest iteration when the search is only limited by time
+const MAX_TIMED_DEPTH: u8 = 4;
+/// Search time before the root move being searched is reported
+const CURRMOVE_DELAY: Duration = Duration::from_millis(1000);
+/// Shortest time between reports of the root move being searched
+const CURRMOVE_INTERVAL: Duration = Duration::from_millis(100);
 
 pub struct Report {
     pub nodes_visited: u64,
+    /// Deepest ply from the root of any visited node
+    pub seldepth: u8,
+    /// Positions found in the tablebases
+    pub tb_hits: u64,
+    /// Set to abandon the search, whose scores are then meaningless
+    pub stop: Option<Arc<AtomicBool>>,
+    /// Best line found from the node at each ply, the triangular PV table
+    pub pv: Vec<Vec<Move>>,
+}
+
+impl Report {
+    /// Start the line at `ply` over, empty until a move is searched from there
+    fn clear_pv(&mut self, ply: u8) {
+        let ply = ply as usize;
+        if self.pv.len() <= ply + 1 {
+            self.pv.resize(ply + 2, Vec::new());
+        }
+        self.pv[ply].clear();
+    }
+
+    /// Make `mv` followed by the line from the next ply the line at `ply`
+    fn update_pv(&mut self, ply: u8, mv: Move) {
+        let (line, rest) = self.pv.split_at_mut(ply as usize + 1);
+        let line = &mut line[ply as usize];
+        line.clear();
+        line.push(mv);
+        line.extend_from_slice(&rest[0]);
+    }
+
+    fn stopped(&self) -> bool {
+        self.stop
+            .as_ref()
+            .is_some_and(|stop| stop.load(Ordering::Relaxed))
+    }
+}
+
+/// Iterative deepening over a full-width negamax search
+pub struct Negamax;
+
+impl SearchAlgorithm for Negamax {
+    fn search(
+        &mut self,
+        position: &VariantPosition,
+        limits: &Limits,
+        ctx: &mut SearchContext,
+    ) -> Move {
+        let skill = ctx.options.skill;
+        let mut max_depth = limits.depth.unwrap_or(MAX_TIMED_DEPTH).max(1);
+        if !skill.is_full_strength() {
+            max_depth = max_depth.min(skill.depth());
+        }
+        let mut last_currmove = None;
+
+        // In endgames covered by the tablebases only moves keeping the best result are searched
+        let root_moves = ctx
+            .tablebases
+            .and_then(|tablebases| tablebases.root_moves(position));
+        let root = match root_moves {
+            Some((moves, tb_hits)) => Root { moves, tb_hits },
+            None => Root {
+                moves: position.legal_moves().to_vec(),
+                tb_hits: 0,
+            },
+        };
+
+        // The first iteration always completes so there is a move to play
+        let mut search_depth = 1;
+        let (mut scored_moves, mut nodes) = find_best_move(
+            position,
+            &root,
+            search_depth,
+            0,
+            limits,
+            &mut last_currmove,
+            ctx,
+        )
+        .expect("the first iteration is never stopped");
+        while search_depth < max_depth
+            && !limits.time_up()
+            && !limits.stopped()
+            && (skill.is_full_strength() || nodes < skill.nodes())
+        {
+            search_depth += 1;
+            // A stopped iteration is dropped for the last complete one
+            match find_best_move(
+                position,
+                &root,
+                search_depth,
+                nodes,
+                limits,
+                &mut last_currmove,
+                ctx,
+            ) {
+                Some(result) => (scored_moves, nodes) = result,
+                None => break,
+            }
+        }
+
+        skill.pick(&scored_moves)
+    }
+}
+
+/// Moves searched at the root
+struct Root {
+    moves: Vec<Move>,
+    /// DTZ probes made to choose the moves
+    tb_hits: u64,
+}
+
+/// Score every root move at `search_depth`, best first, and count the nodes searched so far,
+/// adding to the `nodes` of earlier iterations.
+/// `None` if the search was stopped before it finished, which the first iteration never is.
+fn find_best_move(
+    position: &VariantPosition,
+    root: &Root,
+    search_depth: u8,
+    nodes: u64,
+    limits: &Limits,
+    last_currmove: &mut Option<Instant>,
+    ctx: &mut SearchContext,
+) -> Option<(Vec<(Move, i32)>, u64)> {
+    let mut report = Report {
+        nodes_visited: nodes,
+        seldepth: 0,
+        tb_hits: root.tb_hits,
+        stop: (search_depth > 1).then(|| limits.stop.clone()),
+        pv: Vec::new(),
+    };
+    // Each root move with its score and the line it starts
+    let mut scored_lines = Vec::new();
+
+    for (i, &mv) in root.moves.iter().enumerate() {
+        // Show progress through the root moves in long searches, but not too often
+        let due = last_currmove.is_none_or(|last| last.elapsed() >= CURRMOVE_INTERVAL);
+        if limits.start.elapsed() >= CURRMOVE_DELAY && due {
+            *last_currmove = Some(Instant::now());
+            // The controller may have quit, the search then ends at the next stop check
+            let _ = ctx.info_tx.send(SearchInfo::CurrMove {
+                depth: search_depth,
+                mv,
+                number: i as u16 + 1,
+            });
+        }
+
+        ctx.evaluator.make_move(position, mv);
+        let result_position = position.clone().play(mv).unwrap();
+        let score = -negamax(
+            result_position,
+            search_depth - 1,
+            1,
+            ctx.evaluator,
+            ctx.tt,
+            ctx.tablebases,
+            &mut report,
+        );
+        ctx.evaluator.unmake_move();
+
+        if report.stopped() {
+            return None;
+        }
+        report.update_pv(0, mv);
+        scored_lines.push((mv, score, report.pv[0].clone()));
+    }
+
+    // Best first, keeping the move order among equal scores
+    scored_lines.sort_by_key(|&(_, score, _)| std::cmp::Reverse(score));
+
+    for (i, (_, score, line)) in scored_lines
+        .iter()
+        .take(ctx.options.multi_pv as usize)
+        .enumerate()
+    {
+        let _ = ctx.info_tx.send(SearchInfo::Info {
+            depth: search_depth,
+            seldepth: report.seldepth,
+            multipv: i as u8 + 1,
+            pv: line.clone(),
+            score: *score,
+            nodes: report.nodes_visited,
+            time: limits.start.elapsed(),
+            hashfull: Some(ctx.tt.hashfull()),
+            tbhits: report.tb_hits,
+        });
+    }
+
+    let scored_moves = scored_lines
+        .into_iter()
+        .map(|(mv, score, _)| (mv, score))
+        .collect();
+    Some((scored_moves, report.nodes_visited))
 }
 
 /// what is the value at the root of the game tree? returns (value, nodes visited)
-pub fn negamax(position: Chess, depth: u8, eval: fn(&Chess) -> i32, report: &mut Report) -> i32 {
+///
+/// `depth` is what remains to be searched, `ply` how far `position` is from the root. The best
+/// line from `position` is left in `report.pv[ply]`. It ends early where a score comes from the
+/// transposition table or the tablebases, which keep no moves.
+pub fn negamax<P: Position + Clone>(
+    position: P,
+    depth: u8,
+    ply: u8,
+    eval: &mut dyn Evaluator<P>,
+    tt: &mut TranspositionTable,
+    tablebases: Option<&Tablebases>,
+    report: &mut Report,
+) -> i32 {
     report.nodes_visited += 1;
-    if depth == 0 || position.is_game_over() {
-        return eval(&position);
+    report.clear_pv(ply);
+    if report.stopped() {
+        return 0;
+    }
+    report.seldepth = report.seldepth.max(ply);
+    let game_over = position.is_game_over();
+
+    // The exact result of an endgame ends the search of it
+    if let Some(score) = tablebases
+        .filter(|_| !game_over)
+        .and_then(|tablebases| tablebases.probe_wdl(&position))
+    {
+        report.tb_hits += 1;
+        return score;
+    }
+
+    if depth == 0 || game_over {
+        return eval.evaluate(&position);
     } else {
+        // Reuse the result of an earlier search of the same position
+        let key = TranspositionTable::key(&position);
+        if let Some(score) = tt.probe(key, depth) {
+            return score;
+        }
+
         // loop over legal moves
         let mut max_value = i32::MIN;
 
         for mv in position.legal_moves() {
-            let result_position = position.clone().play(mv).unwrap();
-            let value = -negamax(result_position, depth - 1, eval, report);
+            eval.make_move(&position, mv);
+            let mut result_position = position.clone();
+            result_position.play_unchecked(mv);
+            let value = -negamax(
+                result_position,
+                depth - 1,
+                ply + 1,
+                eval,
+                tt,
+                tablebases,
+                report,
+            );
+            eval.unmake_move();
 
             if value > max_value {
                 max_value = value;
+                report.update_pv(ply, mv);
             }
         }
+
+        // Scores below a stopped search are incomplete
+        if !report.stopped() {
+            tt.store(key, depth, max_value);
+        }
         return max_value;
     }
 }
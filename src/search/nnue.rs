@@ -0,0 +1,316 @@
+//! Efficiently updatable neural network evaluation.
+//!
+//! The network has 768 inputs per perspective (6 roles x 2 colors x 64 squares), one hidden
+//! layer shared by both perspectives and a single output. The hidden layer (the accumulator)
+//! is updated incrementally as moves are made and unmade during the search.
+//!
+//! Of the HalfKP and 768-input layouts this is the 768-input one. HalfKP keys the inputs on the
+//! own king's square as well, which needs about 64 times the feature weights and a refresh of
+//! the accumulator on every king move, and would need a different file format.
+//!
+//! Network files are little-endian and laid out as follows:
+//!
+//! | Field           | Type  | Count          |
+//! |-----------------|-------|----------------|
+//! | Magic `KNUE`    | u8    | 4              |
+//! | Hidden size `H` | u32   | 1              |
+//! | Feature weights | i16   | 768 * H        |
+//! | Feature biases  | i16   | H              |
+//! | Output weights  | i16   | 2 * H          |
+//! | Output bias     | i32   | 1              |
+//!
+//! Feature weights and biases are quantized by `QA`, output weights by `QB` and the output
+//! bias by `QA * QB`.
+
+use crate::search::evaluator::{terminal_score, Evaluator};
+use crate::search::tablebase::TB_WIN_SCORE;
+use shakmaty::{Board, CastlingSide, Color, Move, Piece, Position, Role, Square};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+const MAGIC: &[u8; 4] = b"KNUE";
+const INPUTS: usize = 768;
+const QA: i32 = 255;
+const QB: i32 = 64;
+const SCALE: i32 = 400;
+/// Largest score the network gives, below tablebase wins and mates
+const MAX_SCORE: i32 = TB_WIN_SCORE - 1;
+
+/// Deepest search path the accumulator stack has room for
+const MAX_PLY: usize = 256;
+
+/// Network embedded at build time from `assets/net.nnue`, empty if there was none
+static EMBEDDED: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/net.nnue"));
+
+/// Quantized network weights
+pub struct Network {
+    hidden: usize,
+    feature_weights: Vec<i16>,
+    feature_biases: Vec<i16>,
+    output_weights: Vec<i16>,
+    output_bias: i32,
+}
+
+/// Reasons a network file can't be used
+#[derive(Debug)]
+pub enum NetworkError {
+    Io(std::io::Error),
+    BadMagic,
+    BadSize { expected: usize, actual: usize },
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetworkError::Io(err) => write!(f, "{err}"),
+            NetworkError::BadMagic => write!(f, "not a network file"),
+            NetworkError::BadSize { expected, actual } => {
+                write!(f, "expected {expected} bytes, found {actual}")
+            }
+        }
+    }
+}
+
+impl Network {
+    /// Load a network from a file
+    pub fn load(path: impl AsRef<Path>) -> Result<Network, NetworkError> {
+        let bytes = fs::read(path).map_err(NetworkError::Io)?;
+        Network::from_bytes(&bytes)
+    }
+
+    /// The network embedded at build time, if any
+    pub fn embedded() -> Option<Network> {
+        if EMBEDDED.is_empty() {
+            return None;
+        }
+        Network::from_bytes(EMBEDDED).ok()
+    }
+
+    /// Parse a network in the file format described in the module documentation
+    pub fn from_bytes(bytes: &[u8]) -> Result<Network, NetworkError> {
+        if bytes.len() < 8 || &bytes[..4] != MAGIC {
+            return Err(NetworkError::BadMagic);
+        }
+        let hidden = u32::from_le_bytes(bytes[4..8].try_into().unwrap()) as usize;
+
+        let expected = 8 + 2 * (INPUTS * hidden + hidden + 2 * hidden) + 4;
+        if bytes.len() != expected {
+            return Err(NetworkError::BadSize {
+                expected,
+                actual: bytes.len(),
+            });
+        }
+
+        let mut offset = 8;
+        let mut read_i16s = |count: usize| -> Vec<i16> {
+            let values = bytes[offset..offset + 2 * count]
+                .chunks_exact(2)
+                .map(|chunk| i16::from_le_bytes([chunk[0], chunk[1]]))
+                .collect();
+            offset += 2 * count;
+            values
+        };
+
+        let feature_weights = read_i16s(INPUTS * hidden);
+        let feature_biases = read_i16s(hidden);
+        let output_weights = read_i16s(2 * hidden);
+        let output_bias = i32::from_le_bytes(bytes[expected - 4..].try_into().unwrap());
+
+        Ok(Network {
+            hidden,
+            feature_weights,
+            feature_biases,
+            output_weights,
+            output_bias,
+        })
+    }
+
+    fn weights(&self, feature: usize) -> &[i16] {
+        &self.feature_weights[feature * self.hidden..(feature + 1) * self.hidden]
+    }
+}
+
+/// Input index of a piece on a square, seen from one side
+fn feature(perspective: Color, piece: Piece, sq: Square) -> usize {
+    let (sq, side) = match perspective {
+        Color::White => (sq, piece.color),
+        Color::Black => (sq.flip_vertical(), piece.color.other()),
+    };
+    let side = if side == Color::White { 0 } else { 1 };
+    (side * 6 + (piece.role as usize - 1)) * 64 + sq.to_usize()
+}
+
+/// Hidden layer values for both perspectives
+#[derive(Clone)]
+struct Accumulator {
+    white: Vec<i16>,
+    black: Vec<i16>,
+}
+
+impl Accumulator {
+    fn get_mut(&mut self, perspective: Color) -> &mut Vec<i16> {
+        match perspective {
+            Color::White => &mut self.white,
+            Color::Black => &mut self.black,
+        }
+    }
+}
+
+/// Accumulators along the current search path
+pub struct NnueState {
+    network: Arc<Network>,
+    stack: Vec<Accumulator>,
+    ply: usize,
+}
+
+impl NnueState {
+    pub fn new(network: Arc<Network>) -> Self {
+        let empty = Accumulator {
+            white: vec![0; network.hidden],
+            black: vec![0; network.hidden],
+        };
+        NnueState {
+            stack: vec![empty; MAX_PLY + 1],
+            ply: 0,
+            network,
+        }
+    }
+
+    /// Compute the accumulator of the root position from scratch
+    pub fn refresh(&mut self, board: &Board) {
+        self.ply = 0;
+        let network = &self.network;
+        let acc = &mut self.stack[0];
+
+        for perspective in Color::ALL {
+            let values = acc.get_mut(perspective);
+            values.copy_from_slice(&network.feature_biases);
+            for (sq, piece) in board {
+                add(values, network.weights(feature(perspective, piece, sq)));
+            }
+        }
+    }
+
+    /// Update the accumulator for a move about to be played in `position`
+    pub fn push<P: Position>(&mut self, position: &P, mv: Move) {
+        let us = position.turn();
+        let mut added: [Option<(Piece, Square)>; 2] = [None; 2];
+        let mut removed: [Option<(Piece, Square)>; 2] = [None; 2];
+
+        match mv {
+            Move::Normal {
+                role,
+                from,
+                capture,
+                to,
+                promotion,
+            } => {
+                removed[0] = Some((role.of(us), from));
+                removed[1] = capture.map(|captured| (captured.of(us.other()), to));
+                added[0] = Some((promotion.unwrap_or(role).of(us), to));
+            }
+            Move::EnPassant { from, to } => {
+                let captured = Square::from_coords(to.file(), from.rank());
+                removed[0] = Some((Role::Pawn.of(us), from));
+                removed[1] = Some((Role::Pawn.of(us.other()), captured));
+                added[0] = Some((Role::Pawn.of(us), to));
+            }
+            Move::Castle { king, rook } => {
+                let side = CastlingSide::from_queen_side(rook < king);
+                removed[0] = Some((Role::King.of(us), king));
+                removed[1] = Some((Role::Rook.of(us), rook));
+                added[0] = Some((Role::King.of(us), side.king_to(us)));
+                added[1] = Some((Role::Rook.of(us), side.rook_to(us)));
+            }
+            Move::Put { role, to } => added[0] = Some((role.of(us), to)),
+        }
+
+        let (done, rest) = self.stack.split_at_mut(self.ply + 1);
+        let (prev, next) = (&done[self.ply], &mut rest[0]);
+        next.clone_from(prev);
+
+        for perspective in Color::ALL {
+            let values = next.get_mut(perspective);
+            for &(piece, sq) in removed.iter().flatten() {
+                sub(
+                    values,
+                    self.network.weights(feature(perspective, piece, sq)),
+                );
+            }
+            for &(piece, sq) in added.iter().flatten() {
+                add(
+                    values,
+                    self.network.weights(feature(perspective, piece, sq)),
+                );
+            }
+        }
+
+        self.ply += 1;
+    }
+
+    /// Restore the accumulator from before the last move
+    pub fn pop(&mut self) {
+        self.ply -= 1;
+    }
+
+    /// Evaluate the current position for the player who is about to move
+    pub fn evaluate(&self, turn: Color) -> i32 {
+        let acc = &self.stack[self.ply];
+        let (us, them) = match turn {
+            Color::White => (&acc.white, &acc.black),
+            Color::Black => (&acc.black, &acc.white),
+        };
+
+        // Each product fits in an i32 but their sum over a large hidden layer may not
+        let hidden = self.network.hidden;
+        let weights = &self.network.output_weights;
+        let mut sum: i64 = 0;
+        for i in 0..hidden {
+            sum += (crelu(us[i]) * weights[i] as i32) as i64;
+            sum += (crelu(them[i]) * weights[hidden + i] as i32) as i64;
+        }
+
+        let score = (sum + self.network.output_bias as i64) * SCALE as i64 / (QA * QB) as i64;
+        score.clamp(-MAX_SCORE as i64, MAX_SCORE as i64) as i32
+    }
+}
+
+/// Only updates correctly for variants where moves change the board like in standard chess
+impl<P: Position> Evaluator<P> for NnueState {
+    fn reset(&mut self, position: &P) {
+        self.refresh(position.board());
+    }
+
+    fn make_move(&mut self, position: &P, mv: Move) {
+        self.push(position, mv);
+    }
+
+    fn unmake_move(&mut self) {
+        self.pop();
+    }
+
+    fn evaluate(&mut self, position: &P) -> i32 {
+        if let Some(score) = terminal_score(position) {
+            return score;
+        }
+        NnueState::evaluate(self, position.turn())
+    }
+}
+
+fn crelu(value: i16) -> i32 {
+    (value as i32).clamp(0, QA)
+}
+
+fn add(values: &mut [i16], weights: &[i16]) {
+    for (value, weight) in values.iter_mut().zip(weights) {
+        *value = value.wrapping_add(*weight);
+    }
+}
+
+fn sub(values: &mut [i16], weights: &[i16]) {
+    for (value, weight) in values.iter_mut().zip(weights) {
+        *value = value.wrapping_sub(*weight);
+    }
+}
@@ -0,0 +1,73 @@
+//! Move generation verification by counting the leaf nodes of the move tree.
+
+use shakmaty::uci::UciMove;
+use shakmaty::{CastlingMode, Move, Position};
+use std::fmt;
+
+/// Number of leaf nodes `depth` plies below `position`.
+///
+/// Moves are applied the same way as in the search, so this also checks that.
+pub fn perft<P: Position + Clone>(position: &P, depth: u8) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let moves = position.legal_moves();
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+
+    moves
+        .into_iter()
+        .map(|mv| perft(&play(position, mv), depth - 1))
+        .sum()
+}
+
+/// The position after a legal move
+fn play<P: Position + Clone>(position: &P, mv: Move) -> P {
+    let mut next = position.clone();
+    next.play_unchecked(mv);
+    next
+}
+
+/// Leaf node count below each legal move
+pub struct Divide {
+    pub moves: Vec<(Move, u64)>,
+    /// How castling moves are written
+    pub mode: CastlingMode,
+}
+
+impl Divide {
+    /// Total number of leaf nodes
+    pub fn nodes(&self) -> u64 {
+        self.moves.iter().map(|(_, nodes)| nodes).sum()
+    }
+}
+
+/// Perft split by root move, for finding where two move generators disagree. At depth 0 no move
+/// is played, so there is nothing to split.
+pub fn divide<P: Position + Clone>(position: &P, depth: u8, mode: CastlingMode) -> Divide {
+    if depth == 0 {
+        return Divide {
+            moves: Vec::new(),
+            mode,
+        };
+    }
+    let moves = position
+        .legal_moves()
+        .into_iter()
+        .map(|mv| (mv, perft(&play(position, mv), depth - 1)))
+        .collect();
+    Divide { moves, mode }
+}
+
+/// Formats like other engines do, one move per line followed by the total
+impl fmt::Display for Divide {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (mv, nodes) in &self.moves {
+            writeln!(f, "{}: {nodes}", UciMove::from_move(*mv, self.mode))?;
+        }
+        writeln!(f)?;
+        write!(f, "Nodes searched: {}", self.nodes())
+    }
+}
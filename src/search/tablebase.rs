@@ -0,0 +1,111 @@
+//! Syzygy endgame tablebases.
+//!
+//! At the root, DTZ tables narrow the moves down to those keeping the best result, making the
+//! most progress when winning. Inside the search, WDL tables give the result of positions with
+//! few enough pieces, right after a capture or pawn move.
+
+use shakmaty::{CastlingMode, Chess, EnPassantMode, FromSetup, Move, Position};
+use shakmaty_syzygy::{Tablebase, Wdl};
+use std::io;
+
+/// Score of a position won according to the tablebases, below any mate the search finds
+pub const TB_WIN_SCORE: i32 = 20_000;
+
+/// Tables loaded from the directories of `SyzygyPath`
+pub struct Tablebases {
+    tables: Tablebase<Chess>,
+}
+
+impl Tablebases {
+    /// Load the tables in `paths`, directories separated like in the `PATH` variable
+    pub fn open(paths: &str) -> io::Result<Self> {
+        let mut tables = Tablebase::new();
+        let mut count = 0;
+        for path in std::env::split_paths(paths) {
+            count += tables.add_directory(&path)?;
+        }
+        if count == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no tables in '{paths}'"),
+            ));
+        }
+        Ok(Tablebases { tables })
+    }
+
+    /// Most pieces, kings included, of any loaded table
+    pub fn max_pieces(&self) -> usize {
+        self.tables.max_pieces()
+    }
+
+    /// The position as standard chess, if the tables cover it
+    fn covered<P: Position>(&self, position: &P) -> Option<Chess> {
+        if position.board().occupied().count() > self.max_pieces() || !position.castles().is_empty()
+        {
+            return None;
+        }
+        Chess::from_setup(
+            position.to_setup(EnPassantMode::Legal),
+            CastlingMode::Chess960,
+        )
+        .ok()
+    }
+
+    /// Score of the side to move, if the last move was a capture or pawn move and the tables
+    /// cover the position. Wins and losses spoiled by the fifty-move rule count as draws.
+    pub fn probe_wdl<P: Position>(&self, position: &P) -> Option<i32> {
+        if position.halfmoves() != 0 {
+            return None;
+        }
+        let position = self.covered(position)?;
+        let score = match self.tables.probe_wdl_after_zeroing(&position).ok()? {
+            Wdl::Win => TB_WIN_SCORE,
+            Wdl::Loss => -TB_WIN_SCORE,
+            Wdl::CursedWin | Wdl::Draw | Wdl::BlessedLoss => 0,
+        };
+        Some(score)
+    }
+
+    /// Root moves keeping the best result: the fastest wins, all draws, or the slowest losses,
+    /// with the number of probes made to find them. `None` if the tables don't cover the position.
+    pub fn root_moves<P: Position>(&self, position: &P) -> Option<(Vec<Move>, u64)> {
+        let root = self.covered(position)?;
+        let mut probes = 0;
+
+        // Outcome class (2 win, 1 draw, 0 loss) and plies to the next zeroing move, per move
+        let mut ranked = Vec::new();
+        for mv in root.legal_moves() {
+            let after = root.clone().play(mv).ok()?;
+            if after.is_checkmate() {
+                ranked.push((mv, 2, 0));
+                continue;
+            }
+            // Distance of the opponent, negative if they lose
+            let dtz = self.tables.probe_dtz(&after).ok()?.ignore_rounding().0;
+            probes += 1;
+            let plies = dtz.abs() + 1;
+            let within_fifty_moves = dtz.abs() + after.halfmoves() as i32 <= 100;
+            let class = match dtz {
+                dtz if dtz < 0 && within_fifty_moves => 2,
+                dtz if dtz > 0 && within_fifty_moves => 0,
+                _ => 1,
+            };
+            ranked.push((mv, class, plies));
+        }
+
+        let best_class = ranked.iter().map(|&(_, class, _)| class).max()?;
+        ranked.retain(|&(_, class, _)| class == best_class);
+        let target = match best_class {
+            2 => ranked.iter().map(|&(_, _, plies)| plies).min(),
+            0 => ranked.iter().map(|&(_, _, plies)| plies).max(),
+            _ => None,
+        };
+
+        let moves = ranked
+            .into_iter()
+            .filter(|&(_, _, plies)| target.is_none_or(|target| plies == target))
+            .map(|(mv, _, _)| mv)
+            .collect();
+        Some((moves, probes))
+    }
+}
@@ -0,0 +1,69 @@
+use shakmaty::zobrist::Zobrist64;
+use shakmaty::{EnPassantMode, Position};
+use std::mem;
+
+/// A stored search result
+#[derive(Clone, Copy, Default)]
+struct Entry {
+    key: u64,
+    depth: u8,
+    score: i32,
+    used: bool,
+}
+
+/// Fixed size cache of search results, indexed by Zobrist hash
+pub struct TranspositionTable {
+    entries: Vec<Entry>,
+}
+
+impl TranspositionTable {
+    /// Create a table taking up about `size_mb` megabytes
+    pub fn new(size_mb: usize) -> Self {
+        let count = (size_mb * 1024 * 1024 / mem::size_of::<Entry>()).max(1);
+        TranspositionTable {
+            entries: vec![Entry::default(); count],
+        }
+    }
+
+    /// Hash key of a position
+    pub fn key<P: Position>(position: &P) -> u64 {
+        position.zobrist_hash::<Zobrist64>(EnPassantMode::Legal).0
+    }
+
+    /// Score of a position searched at least `depth` deep, if there is one
+    pub fn probe(&self, key: u64, depth: u8) -> Option<i32> {
+        let entry = &self.entries[self.index(key)];
+        (entry.used && entry.key == key && entry.depth >= depth).then_some(entry.score)
+    }
+
+    /// Store the score of a position, keeping deeper results of the same position
+    pub fn store(&mut self, key: u64, depth: u8, score: i32) {
+        let index = self.index(key);
+        let entry = &mut self.entries[index];
+        if entry.used && entry.key == key && entry.depth > depth {
+            return;
+        }
+        *entry = Entry {
+            key,
+            depth,
+            score,
+            used: true,
+        };
+    }
+
+    /// Remove all entries
+    pub fn clear(&mut self) {
+        self.entries.fill(Entry::default());
+    }
+
+    /// Used entries per mille, sampled from the start of the table
+    pub fn hashfull(&self) -> u16 {
+        let sample = &self.entries[..self.entries.len().min(1000)];
+        let used = sample.iter().filter(|entry| entry.used).count();
+        (used * 1000 / sample.len()) as u16
+    }
+
+    fn index(&self, key: u64) -> usize {
+        (key % self.entries.len() as u64) as usize
+    }
+}
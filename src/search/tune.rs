@@ -0,0 +1,142 @@
+//! Texel tuning of the evaluation weights, run by the `tune` binary.
+//!
+//! The evaluation is linear in the weights, so each labeled position is reduced once to the
+//! coefficient of every weight and the part that doesn't depend on them. The weights are then
+//! fitted so a sigmoid of the evaluation predicts the game results.
+
+use crate::search::endgame::SCALE_NORMAL;
+use crate::search::eval::{self, EvalParams, Score, DEFAULT_PARAMS, MAX_PHASE};
+use shakmaty::{Chess, Position};
+
+/// A quiet position with its game result and the coefficient of every weight in its evaluation
+pub struct Entry {
+    /// Game result from white's point of view: 1.0, 0.5 or 0.0
+    pub result: f64,
+    /// Evaluation (from white's point of view) is `offset` plus the sum of `weight * coefficient`
+    pub coefficients: Vec<(usize, f64)>,
+    /// Endgame knowledge and variant goals, which don't depend on the weights
+    pub offset: f64,
+}
+
+impl Entry {
+    pub fn new(position: &Chess, result: f64) -> Self {
+        let weight_count = zero_params().weights_mut().len();
+        let fixed = eval::trace_with(position, &zero_params());
+        let taper = |trace: &eval::Trace, score: Score| {
+            let phase = trace.phase as f64 / MAX_PHASE as f64;
+            let scale = trace.scale as f64 / SCALE_NORMAL as f64;
+            (score.mg as f64 * phase + score.eg as f64 * (1.0 - phase)) * scale
+        };
+
+        // Each coefficient is the evaluation with only that weight set to one, less the fixed part
+        let mut coefficients = Vec::new();
+        for i in 0..weight_count {
+            let mut params = zero_params();
+            *params.weights_mut()[i] = 1;
+
+            let trace = eval::trace_with(position, &params);
+            let coefficient = taper(&trace, trace.score() - fixed.score());
+            if coefficient != 0.0 {
+                coefficients.push((i, coefficient));
+            }
+        }
+
+        Entry {
+            result,
+            coefficients,
+            offset: taper(&fixed, fixed.score()),
+        }
+    }
+
+    /// Evaluation from white's point of view with the given weights
+    pub fn evaluate(&self, weights: &[f64]) -> f64 {
+        self.offset
+            + self
+                .coefficients
+                .iter()
+                .map(|&(i, coefficient)| weights[i] * coefficient)
+                .sum::<f64>()
+    }
+}
+
+fn zero_params() -> EvalParams {
+    let mut params = DEFAULT_PARAMS;
+    for weight in params.weights_mut() {
+        *weight = 0;
+    }
+    params
+}
+
+/// Captures-only search, returning the score and the quiet position at the end of the principal variation
+pub fn quiesce(position: &Chess, mut alpha: i32, beta: i32) -> (i32, Chess) {
+    let stand_pat = eval::eval(position);
+    if stand_pat >= beta {
+        return (stand_pat, position.clone());
+    }
+
+    let mut best = position.clone();
+    alpha = alpha.max(stand_pat);
+
+    for mv in position.capture_moves() {
+        let result_position = position.clone().play(mv).unwrap();
+        let (score, leaf) = quiesce(&result_position, -beta, -alpha);
+        let score = -score;
+
+        if score >= beta {
+            return (score, leaf);
+        }
+        if score > alpha {
+            alpha = score;
+            best = leaf;
+        }
+    }
+
+    (alpha, best)
+}
+
+/// Maps an evaluation to an expected score
+pub fn sigmoid(k: f64, eval: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-k * eval / 400.0))
+}
+
+/// Mean squared difference between game results and expected scores
+pub fn error(entries: &[Entry], weights: &[f64], k: f64) -> f64 {
+    let total: f64 = entries
+        .iter()
+        .map(|entry| (entry.result - sigmoid(k, entry.evaluate(weights))).powi(2))
+        .sum();
+    total / entries.len() as f64
+}
+
+pub fn gradient(entries: &[Entry], weights: &[f64], k: f64) -> Vec<f64> {
+    let mut gradient = vec![0.0; weights.len()];
+
+    for entry in entries {
+        let s = sigmoid(k, entry.evaluate(weights));
+        let factor = (entry.result - s) * s * (1.0 - s);
+        for &(i, coefficient) in &entry.coefficients {
+            gradient[i] += factor * coefficient;
+        }
+    }
+
+    let scale = -2.0 * k * 10f64.ln() / 400.0 / entries.len() as f64;
+    gradient.iter().map(|g| g * scale).collect()
+}
+
+/// Finds the sigmoid scaling that best fits the untuned evaluation
+pub fn find_scaling_constant(entries: &[Entry], weights: &[f64]) -> f64 {
+    let (mut low, mut high) = (0.0, 10.0);
+
+    // Ternary search, the error is unimodal in K
+    for _ in 0..100 {
+        let a = low + (high - low) / 3.0;
+        let b = high - (high - low) / 3.0;
+        if error(entries, weights, a) < error(entries, weights, b) {
+            high = b;
+        } else {
+            low = a;
+        }
+    }
+
+    (low + high) / 2.0
+}
@@ -0,0 +1,90 @@
+//! Evaluation terms for the chess variants supported by shakmaty.
+//!
+//! The regular terms still apply to every variant, these only add what matters most in each.
+
+use crate::search::eval::{EvalParams, Score};
+use shakmaty::variant::{Variant, VariantPosition};
+use shakmaty::{Chess, Color, Position, Role, Square};
+
+// Parameters
+/// Per step the king is closer to the center in King of the Hill
+const KOTH_KING_CENTER: Score = Score::new(40, 80);
+/// Per check given in Three-check
+const CHECK_GIVEN: Score = Score::new(150, 150);
+/// Per rank the king has advanced in Racing Kings
+const RACING_KING_RANK: Score = Score::new(50, 80);
+/// Factor of the piece value a piece in hand is worth in Crazyhouse
+const POCKET_FACTOR: i32 = 1;
+
+/// Positions that know which variant they are played in
+pub trait HasVariant: Position {
+    fn variant(&self) -> Variant;
+}
+
+impl HasVariant for Chess {
+    fn variant(&self) -> Variant {
+        Variant::Chess
+    }
+}
+
+impl HasVariant for VariantPosition {
+    fn variant(&self) -> Variant {
+        VariantPosition::variant(self)
+    }
+}
+
+/// Variant specific score of one side
+pub fn eval_variant<P: HasVariant>(position: &P, color: Color, params: &EvalParams) -> Score {
+    let board = position.board();
+    let king = board.king_of(color);
+
+    match position.variant() {
+        // Pieces in hand can be dropped anywhere, so they are worth at least as much as on the board
+        Variant::Crazyhouse => {
+            let Some(pockets) = position.pockets() else {
+                return Score::default();
+            };
+            let mut score = Score::default();
+            for role in Role::ALL {
+                let count = *pockets.get(color).get(role) as i32;
+                score += *params.piece_values.get(role) * (count * POCKET_FACTOR);
+            }
+            score
+        }
+
+        // Bring the king towards the center, where it wins the game
+        Variant::KingOfTheHill => king.map_or(Score::default(), |king| {
+            let distance = [Square::D4, Square::E4, Square::D5, Square::E5]
+                .into_iter()
+                .map(|center| king.distance(center) as i32)
+                .min()
+                .unwrap_or(0);
+            KOTH_KING_CENTER * (3 - distance)
+        }),
+
+        // Each check brings the game closer to a win
+        Variant::ThreeCheck => position
+            .remaining_checks()
+            .map_or(Score::default(), |checks| {
+                CHECK_GIVEN * (3 - u32::from(*checks.get(color)) as i32)
+            }),
+
+        // Race the king to the last rank
+        Variant::RacingKings => king.map_or(Score::default(), |king| {
+            RACING_KING_RANK * u32::from(king.rank()) as i32
+        }),
+
+        // Losing pieces is the goal, so material counts against its owner, twice to outweigh
+        // the regular material term
+        Variant::Antichess => {
+            let material = board.material_side(color);
+            let mut score = Score::default();
+            for role in Role::ALL {
+                score += *params.piece_values.get(role) * -(2 * *material.get(role) as i32);
+            }
+            score
+        }
+
+        Variant::Chess | Variant::Atomic | Variant::Horde => Score::default(),
+    }
+}
@@ -0,0 +1,102 @@
+//! Win, draw and loss probabilities for a score, from a model fitted to game results.
+//!
+//! The win rate is a logistic function of the score, whose center and width depend on the
+//! material left on the board: the same advantage is easier to convert with fewer pieces.
+//! The coefficients are fitted by the `wdl` binary.
+
+use shakmaty::{Position, Role};
+use std::fmt;
+
+/// Coefficients for the scores of `eval::eval`, to be refitted whenever the evaluation changes.
+///
+/// `just wdl-selfplay` fits them: the engine plays 2000 games against itself, both sides
+/// searching to depth 2 after 8 random moves, and the model is fitted to the positions after
+/// the random moves.
+pub const WDL_MODEL: WdlModel = WdlModel {
+    a: [
+        7.65247746107353,
+        66.04971523860657,
+        276.09843531886776,
+        771.5164498381932,
+    ],
+    b: [
+        30.39261804138178,
+        125.49445658949229,
+        221.00031213736608,
+        403.4203587335377,
+    ],
+};
+
+/// Range of material (see `material`) the model distinguishes between
+const MIN_MATERIAL: u32 = 17;
+const MAX_MATERIAL: u32 = 78;
+/// Material the polynomials are normalized to
+const NORM_MATERIAL: f64 = 58.0;
+
+/// Coefficients of the cubic polynomials in material giving the center and the width of the
+/// logistic win rate curve, highest power first
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WdlModel {
+    pub a: [f64; 4],
+    pub b: [f64; 4],
+}
+
+/// Expected outcome in permille, from the point of view of the side to move
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Wdl {
+    pub win: u16,
+    pub draw: u16,
+    pub loss: u16,
+}
+
+impl WdlModel {
+    /// Center and width of the win rate curve
+    fn parameters(&self, material: u32) -> (f64, f64) {
+        let x = material.clamp(MIN_MATERIAL, MAX_MATERIAL) as f64 / NORM_MATERIAL;
+        let poly = |c: &[f64; 4]| ((c[0] * x + c[1]) * x + c[2]) * x + c[3];
+        (poly(&self.a), poly(&self.b).max(1.0))
+    }
+
+    /// Probability of winning with `score` (in centipawns) and `material` on the board
+    pub fn win_rate(&self, score: i32, material: u32) -> f64 {
+        let (a, b) = self.parameters(material);
+        1.0 / (1.0 + ((a - score as f64) / b).exp())
+    }
+
+    pub fn wdl(&self, score: i32, material: u32) -> Wdl {
+        let win = (self.win_rate(score, material) * 1000.0).round() as u16;
+        // Negating `i32::MIN`, used for lost positions, would overflow
+        let loss = (self.win_rate(score.saturating_neg(), material) * 1000.0).round() as u16;
+        let win = win.min(1000 - loss);
+        Wdl {
+            win,
+            draw: 1000 - win - loss,
+            loss,
+        }
+    }
+}
+
+/// Formats the model as Rust source, to replace `WDL_MODEL`
+impl fmt::Display for WdlModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "pub const WDL_MODEL: WdlModel = WdlModel {{")?;
+        writeln!(f, "    a: {:?},", self.a)?;
+        writeln!(f, "    b: {:?},", self.b)?;
+        writeln!(f, "}};")
+    }
+}
+
+/// Material of both sides, counting pawns 1, minor pieces 3, rooks 5 and queens 9
+pub fn material<P: Position>(position: &P) -> u32 {
+    let board = position.board();
+    [
+        (Role::Pawn, 1),
+        (Role::Knight, 3),
+        (Role::Bishop, 3),
+        (Role::Rook, 5),
+        (Role::Queen, 9),
+    ]
+    .into_iter()
+    .map(|(role, value)| board.by_role(role).count() as u32 * value)
+    .sum()
+}
@@ -0,0 +1,42 @@
+//! The bench result is the same on every run, a change to it means the search behaves differently.
+
+mod common;
+
+use common::parse_fen;
+use kaksic::search::bench;
+use shakmaty::Chess;
+
+const FENS: [&str; 2] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+];
+
+fn positions() -> Vec<Chess> {
+    FENS.into_iter().map(parse_fen).collect()
+}
+
+#[test]
+fn signature_is_pinned() {
+    // Update these with a commit that is meant to change how the engine searches or evaluates
+    let result = bench::search(&positions(), 3);
+    assert_eq!(result.nodes, 111795);
+    assert_eq!(result.signature, 0x0179_f99a_3961_72a7);
+}
+
+#[test]
+fn signature_is_deterministic() {
+    let first = bench::search(&positions(), 2);
+    let second = bench::search(&positions(), 2);
+    assert_eq!(first.nodes, second.nodes);
+    assert_eq!(first.signature, second.signature);
+}
+
+#[test]
+fn signature_depends_on_the_positions() {
+    let positions = positions();
+    let both = bench::search(&positions, 2).signature;
+    let first = bench::search(&positions[..1], 2).signature;
+    let reversed: Vec<Chess> = positions.iter().rev().cloned().collect();
+    assert_ne!(both, first);
+    assert_ne!(both, bench::search(&reversed, 2).signature);
+}
@@ -0,0 +1,128 @@
+//! Reading Polyglot opening books.
+
+mod common;
+
+use common::parse_fen;
+use kaksic::bot::book::{self, Book, Entry, Selection};
+use shakmaty::uci::UciMove;
+use shakmaty::{CastlingMode, Chess, Move, Position};
+
+fn parse_move(position: &Chess, uci: &str) -> Move {
+    uci.parse::<UciMove>().unwrap().to_move(position).unwrap()
+}
+
+/// Book file contents with the given moves and weights for `position`
+fn book_bytes(position: &Chess, moves: &[(&str, u16)]) -> Vec<u8> {
+    let key = book::key(position);
+    let mut bytes = Vec::new();
+    for &(uci, weight) in moves {
+        bytes.extend(key.to_be_bytes());
+        bytes.extend(book::encode_move(parse_move(position, uci)).to_be_bytes());
+        bytes.extend(weight.to_be_bytes());
+        bytes.extend(0u32.to_be_bytes());
+    }
+    bytes
+}
+
+#[test]
+fn keys_match_polyglot() {
+    let start = Chess::default();
+    assert_eq!(book::key(&start), 0x463b96181691fc9c);
+
+    let e4 = start.clone().play(parse_move(&start, "e2e4")).unwrap();
+    assert_eq!(book::key(&e4), 0x823c9b50fd114196);
+
+    let d5 = e4.clone().play(parse_move(&e4, "d7d5")).unwrap();
+    assert_eq!(book::key(&d5), 0x0756b94461c50fb0);
+}
+
+#[test]
+fn moves_are_encoded_like_polyglot() {
+    let start = Chess::default();
+    // e2e4: from e2 (row 1, file 4) to e4 (row 3, file 4)
+    assert_eq!(
+        book::encode_move(parse_move(&start, "e2e4")),
+        (1 << 3 | 4) << 6 | (3 << 3 | 4)
+    );
+
+    // Castling goes to the rook, promotions are numbered from the knight
+    let position = parse_fen("r3k2r/1P6/8/8/8/8/8/R3K2R w KQkq - 0 1");
+    assert_eq!(book::encode_move(parse_move(&position, "e1g1")), 4 << 6 | 7);
+    assert_eq!(book::encode_move(parse_move(&position, "e1c1")), 4 << 6);
+    assert_eq!(
+        book::encode_move(parse_move(&position, "b7a8n")),
+        1 << 12 | (6 << 3 | 1) << 6 | 7 << 3
+    );
+}
+
+#[test]
+fn book_moves_for_a_position() {
+    let start = Chess::default();
+    let book = Book::from_bytes(&book_bytes(
+        &start,
+        &[("d2d4", 1), ("e2e4", 3), ("g1f3", 0)],
+    ))
+    .unwrap();
+    assert_eq!(book.len(), 3);
+
+    let moves: Vec<(String, u16)> = book
+        .moves(&start)
+        .into_iter()
+        .map(|(mv, weight)| (mv.to_uci(CastlingMode::Standard).to_string(), weight))
+        .collect();
+    assert_eq!(
+        moves,
+        [("d2d4".into(), 1), ("e2e4".into(), 3), ("g1f3".into(), 0)]
+    );
+
+    assert_eq!(
+        book.choose(&start, Selection::Best),
+        Some(parse_move(&start, "e2e4"))
+    );
+    // Moves without weight are never chosen
+    for _ in 0..50 {
+        let mv = book.choose(&start, Selection::Weighted).unwrap();
+        assert_ne!(mv, parse_move(&start, "g1f3"));
+    }
+
+    // Nothing for other positions
+    let e4 = start.clone().play(parse_move(&start, "e2e4")).unwrap();
+    assert!(book.moves(&e4).is_empty());
+    assert_eq!(book.choose(&e4, Selection::Best), None);
+}
+
+#[test]
+fn truncated_book_is_rejected() {
+    let bytes = book_bytes(&Chess::default(), &[("e2e4", 1)]);
+    assert!(Book::from_bytes(&bytes[..15]).is_err());
+}
+
+#[test]
+fn written_books_read_back() {
+    let start = Chess::default();
+    let e4 = start.clone().play(parse_move(&start, "e2e4")).unwrap();
+    let entry = |position: &Chess, uci: &str, weight: u16| Entry {
+        key: book::key(position),
+        mv: book::encode_move(parse_move(position, uci)),
+        weight,
+        learn: 0,
+    };
+    let mut entries = vec![
+        entry(&e4, "e7e5", 4),
+        entry(&start, "d2d4", 1),
+        entry(&start, "e2e4", 2),
+    ];
+
+    let mut bytes = Vec::new();
+    book::write(&mut bytes, &mut entries).unwrap();
+    assert_eq!(bytes.len(), 3 * 16);
+
+    // Sorted by key, the best move first
+    let book = Book::from_bytes(&bytes).unwrap();
+    let weights: Vec<u16> = book.moves(&start).iter().map(|&(_, w)| w).collect();
+    assert_eq!(weights, [2, 1]);
+    assert_eq!(
+        book.choose(&e4, Selection::Best),
+        Some(parse_move(&e4, "e7e5"))
+    );
+}
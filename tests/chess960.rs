@@ -0,0 +1,179 @@
+//! Fischer Random and Double Fischer Random positions, with castling written as king takes rook.
+
+use kaksic::bot::position::setup_position;
+use kaksic::search::perft::{divide, perft};
+use shakmaty::fen::Fen;
+use shakmaty::variant::Variant;
+use shakmaty::{CastlingMode, Chess, Move, Position};
+use shakmaty_uci::UciMove;
+
+/// Positions with node counts for increasing depths, starting at depth 1,
+/// from https://www.chessprogramming.org/Chess960_Perft_Results
+const SUITE: &[(&str, &[u64])] = &[
+    (
+        "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+        &[21, 528, 12189],
+    ),
+    (
+        "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+        &[21, 807, 18002],
+    ),
+    (
+        "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+        &[20, 479, 10471],
+    ),
+];
+
+/// Back rank of the Chess960 start position with the given Scharnagl number
+fn back_rank(mut n: usize) -> [char; 8] {
+    let mut rank = [' '; 8];
+
+    // Bishops on opposite colors
+    rank[n % 4 * 2 + 1] = 'b';
+    n /= 4;
+    rank[n % 4 * 2] = 'b';
+    n /= 4;
+
+    // Queen and knights on the free squares, counted from the left
+    let mut place = |piece: char, index: usize| {
+        let file = (0..8).filter(|&file| rank[file] == ' ').nth(index).unwrap();
+        rank[file] = piece;
+    };
+    place('q', n % 6);
+    n /= 6;
+    let knights = [
+        (0, 1),
+        (0, 2),
+        (0, 3),
+        (0, 4),
+        (1, 2),
+        (1, 3),
+        (1, 4),
+        (2, 3),
+        (2, 4),
+        (3, 4),
+    ];
+    let (first, second) = knights[n];
+    place('n', second);
+    place('n', first);
+
+    // King between the rooks
+    place('r', 0);
+    place('k', 0);
+    place('r', 0);
+    rank
+}
+
+/// Double Fischer Random start position, castling rights given as rook files
+fn start_fen(white: usize, black: usize) -> String {
+    let rook_files = |rank: &[char; 8]| -> String {
+        (0..8)
+            .filter(|&file| rank[file] == 'r')
+            .map(|file| (b'a' + file as u8) as char)
+            .rev()
+            .collect()
+    };
+    let white = back_rank(white);
+    let black = back_rank(black);
+
+    format!(
+        "{}/pppppppp/8/8/8/8/PPPPPPPP/{} w {}{} - 0 1",
+        black.iter().collect::<String>(),
+        white.iter().collect::<String>().to_uppercase(),
+        rook_files(&white).to_uppercase(),
+        rook_files(&black),
+    )
+}
+
+fn parse_fen(fen: &str) -> Chess {
+    fen.parse::<Fen>()
+        .unwrap()
+        .into_position(CastlingMode::Chess960)
+        .unwrap()
+}
+
+fn uci(mv: Move) -> UciMove {
+    UciMove::from_move(mv, CastlingMode::Chess960)
+}
+
+#[test]
+fn reference_positions() {
+    for &(fen, counts) in SUITE {
+        let position = parse_fen(fen);
+        for (depth, &expected) in (1..).zip(counts) {
+            assert_eq!(perft(&position, depth), expected, "{fen} at depth {depth}");
+        }
+    }
+}
+
+#[test]
+fn standard_start_position_is_number_518() {
+    assert_eq!(
+        start_fen(518, 518),
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1"
+    );
+    assert_eq!(perft(&parse_fen(&start_fen(518, 518)), 3), 8902);
+}
+
+#[test]
+fn double_fischer_random_start_positions() {
+    for (white, black) in [(0, 959), (87, 87), (314, 271), (518, 0), (959, 959)] {
+        let fen = start_fen(white, black);
+        let position = setup_position(
+            fen.parse().ok(),
+            &[],
+            Variant::Chess,
+            CastlingMode::Chess960,
+        )
+        .unwrap();
+
+        // The first moves of both sides can't interact, so with mirrored sides every white move
+        // is followed by the same number of black moves
+        if white == black {
+            let moves = position.legal_moves().len() as u64;
+            assert_eq!(perft(&position, 2), moves * moves, "{fen}");
+        }
+
+        // Every move survives being written for the user interface and read back
+        for mv in position.legal_moves() {
+            let after = position.clone().play(mv).unwrap();
+            for reply in after.legal_moves() {
+                assert_eq!(uci(reply).to_move(&after).unwrap(), reply, "{fen}");
+            }
+        }
+    }
+}
+
+#[test]
+fn castling_is_written_as_king_takes_rook() {
+    // King on b1 and rook on a1: castling queen side moves the king to c1
+    let fen = "r5kr/8/8/8/8/8/8/RK5R w HAha - 0 1";
+    let position = parse_fen(fen);
+    let castles: Vec<String> = position
+        .legal_moves()
+        .into_iter()
+        .filter(|mv| mv.is_castle())
+        .map(|mv| uci(mv).to_string())
+        .collect();
+    assert!(castles.contains(&"b1a1".to_string()), "{castles:?}");
+    assert!(castles.contains(&"b1h1".to_string()), "{castles:?}");
+
+    // And read back when replaying the moves of a `position` command
+    let moves: Vec<UciMove> = ["b1a1", "g8h8"]
+        .iter()
+        .map(|mv| mv.parse().unwrap())
+        .collect();
+    let position = setup_position(
+        fen.parse().ok(),
+        &moves,
+        Variant::Chess,
+        CastlingMode::Chess960,
+    )
+    .unwrap();
+    let result = divide(&position, 1, CastlingMode::Chess960);
+    assert_eq!(result.nodes(), perft(&position, 1));
+    assert_eq!(
+        position.board().king_of(shakmaty::Color::White),
+        Some(shakmaty::Square::C1)
+    );
+}
@@ -0,0 +1,138 @@
+//! Helpers shared by the integration tests, each of which uses only some of them.
+#![allow(dead_code)]
+
+use crossbeam_channel::{unbounded, Receiver, RecvTimeoutError, Sender};
+use kaksic::bot::input::InputListener;
+use kaksic::search::eval::Handcrafted;
+use kaksic::search::Searcher;
+use kaksic::{SearchCommand, SearchInfo};
+use shakmaty::fen::Fen;
+use shakmaty::{CastlingMode, Chess};
+use std::io::{self, Write};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Longest wait for a reply that doesn't depend on searching
+pub const REPLY_TIMEOUT: Duration = Duration::from_millis(500);
+/// Longest wait for a shallow search, or one of the default duration, to end
+pub const SEARCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub fn parse_fen(fen: &str) -> Chess {
+    fen.parse::<Fen>()
+        .unwrap()
+        .into_position(CastlingMode::Standard)
+        .unwrap()
+}
+
+/// Output sink sending each complete line to the test
+pub struct Lines {
+    line_tx: Sender<String>,
+    buffer: Vec<u8>,
+}
+
+impl Write for Lines {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        while let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line).trim_end().to_string();
+            let _ = self.line_tx.send(line);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Where the input lines of a session go
+pub trait Input {
+    fn send_line(&self, line: &str);
+}
+
+impl Input for InputListener {
+    fn send_line(&self, line: &str) {
+        self.forward(line);
+    }
+}
+
+impl Input for Sender<String> {
+    fn send_line(&self, line: &str) {
+        self.send(line.to_string()).unwrap();
+    }
+}
+
+/// The engine driven like a user interface would, through either protocol
+pub struct Session<I> {
+    /// Dropping it closes the input, like the user interface going away
+    pub input: I,
+    pub output: Receiver<String>,
+    pub controller: JoinHandle<()>,
+    pub searcher: JoinHandle<()>,
+}
+
+impl<I: Input> Session<I> {
+    /// Starts a searcher, and a controller run by `run_controller` with its output sent to the test
+    pub fn spawn<F>(input: I, run_controller: F) -> Self
+    where
+        F: FnOnce(Sender<SearchCommand>, Receiver<SearchInfo>, Lines) + Send + 'static,
+    {
+        let (cmd_tx, cmd_rx) = unbounded();
+        let (info_tx, info_rx) = unbounded();
+        let (line_tx, output) = unbounded();
+
+        let searcher = thread::spawn(|| {
+            Searcher::new(cmd_rx, info_tx, Box::new(Handcrafted::default())).run()
+        });
+        let controller = thread::spawn(move || {
+            let lines = Lines {
+                line_tx,
+                buffer: Vec::new(),
+            };
+            run_controller(cmd_tx, info_rx, lines)
+        });
+
+        Session {
+            input,
+            output,
+            controller,
+            searcher,
+        }
+    }
+
+    pub fn send(&self, line: &str) {
+        self.input.send_line(line);
+    }
+
+    pub fn next_line(&self, timeout: Duration) -> String {
+        match self.output.recv_timeout(timeout) {
+            Ok(line) => line,
+            Err(RecvTimeoutError::Timeout) => panic!("no reply within {timeout:?}"),
+            Err(RecvTimeoutError::Disconnected) => panic!("controller stopped"),
+        }
+    }
+
+    /// The next lines must be exactly these
+    pub fn expect(&self, expected: &[&str]) {
+        for &line in expected {
+            assert_eq!(self.next_line(REPLY_TIMEOUT), line);
+        }
+    }
+
+    pub fn quit(self) {
+        self.send("quit");
+        finish(self.controller, self.searcher);
+    }
+}
+
+/// Waits for both engine threads to end, neither of them panicking on the way out
+pub fn finish(controller: JoinHandle<()>, searcher: JoinHandle<()>) {
+    let start = Instant::now();
+    while !controller.is_finished() || !searcher.is_finished() {
+        assert!(start.elapsed() < REPLY_TIMEOUT, "engine still running");
+        thread::sleep(Duration::from_millis(5));
+    }
+    assert!(controller.join().is_ok(), "controller panicked");
+    assert!(searcher.join().is_ok(), "searcher panicked");
+}
@@ -0,0 +1,82 @@
+//! Crash reports and the commands kept for them.
+
+use kaksic::bot::crash::{self, Recent, RECENT_COMMANDS};
+
+#[test]
+fn report_shows_the_panic_position_and_commands() {
+    crash::record_command("uci");
+    crash::record_command("position startpos moves e2e4");
+    crash::record_position("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1".into());
+
+    let report = crash::report("index out of bounds", "src/search/negamax.rs:12:5");
+    let lines: Vec<&str> = report.lines().collect();
+    assert!(lines[0].starts_with("------ Crash at "), "{report}");
+    assert!(
+        lines[1].ends_with("panicked at src/search/negamax.rs:12:5: index out of bounds"),
+        "{report}"
+    );
+    assert_eq!(
+        lines[2..6],
+        [
+            "Position: rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+            "Last commands:",
+            "  uci",
+            "  position startpos moves e2e4",
+        ]
+    );
+    assert_eq!(lines[6], "Backtrace:");
+}
+
+#[test]
+fn lines_split_across_chunks_are_joined() {
+    let mut recent = Recent::default();
+    assert!(!recent.remember(b"setoption name UCI_Variant value Crazy"));
+    assert_eq!(recent.commands().count(), 0);
+    assert!(!recent.remember(b"house\r\n\nposition startpos\nis"));
+    assert!(!recent.remember(b"ready\n"));
+    assert_eq!(
+        recent.commands().collect::<Vec<_>>(),
+        [
+            "setoption name UCI_Variant value Crazyhouse",
+            "position startpos",
+            "isready",
+        ]
+    );
+}
+
+#[test]
+fn only_a_quit_line_quits() {
+    let mut recent = Recent::default();
+    assert!(!recent.remember(b"setoption name Book File value quit.bin\n"));
+    assert!(!recent.remember(b"qu"));
+    assert!(recent.remember(b"it\n"));
+    let mut recent = Recent::default();
+    assert!(recent.remember(b"stop\n  QUIT \n"));
+    // Commands keep their case, only the check ignores it
+    assert_eq!(recent.commands().collect::<Vec<_>>(), ["stop", "QUIT"]);
+}
+
+#[test]
+fn keeps_the_last_commands() {
+    let mut recent = Recent::default();
+    for n in 0..RECENT_COMMANDS + 5 {
+        recent.remember(format!("go nodes {n}\n").as_bytes());
+    }
+    let commands: Vec<&str> = recent.commands().collect();
+    assert_eq!(commands.len(), RECENT_COMMANDS);
+    assert_eq!(commands[0], "go nodes 5");
+    assert_eq!(
+        commands[RECENT_COMMANDS - 1],
+        format!("go nodes {}", RECENT_COMMANDS + 4)
+    );
+}
+
+#[test]
+fn invalid_utf8_is_kept_readable() {
+    let mut recent = Recent::default();
+    recent.remember(b"position fen \xff\n");
+    assert_eq!(
+        recent.commands().collect::<Vec<_>>(),
+        ["position fen \u{fffd}"]
+    );
+}
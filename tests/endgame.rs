@@ -0,0 +1,93 @@
+//! Endgame knowledge: the KPK bitbase, mop-up and draw scaling.
+
+mod common;
+
+use common::parse_fen;
+use kaksic::search::endgame::SCALE_NORMAL;
+use kaksic::search::eval::{eval, trace, DEFAULT_PARAMS};
+use kaksic::search::Limits;
+use kaksic::Engine;
+use shakmaty::fen::Fen;
+use shakmaty::{EnPassantMode, Position};
+
+#[test]
+fn kpk_wins_and_draws() {
+    let wins = [
+        // King on the sixth in front of the pawn, whoever is to move
+        "4k3/8/4K3/4P3/8/8/8/8 w - - 0 1",
+        "4k3/8/4K3/4P3/8/8/8/8 b - - 0 1",
+        // The defending king is outside the square of the pawn
+        "8/k7/8/8/8/8/7P/K7 w - - 0 1",
+        // Mirrored for black
+        "8/8/8/8/4p3/4k3/8/4K3 b - - 0 1",
+    ];
+    for fen in wins {
+        let total = trace(&parse_fen(fen)).total();
+        assert!(total.abs() > 500, "{fen}: {total}");
+    }
+
+    let draws = [
+        // The defending king in front of the pawn
+        "8/8/8/8/8/4k3/4P3/4K3 w - - 0 1",
+        // Rook pawn with the defending king in the corner
+        "k7/8/8/8/8/8/P7/K7 w - - 0 1",
+        "7k/8/8/8/8/8/7P/7K w - - 0 1",
+        // The pawn is lost
+        "8/8/8/8/8/8/P7/1k5K b - - 0 1",
+    ];
+    for fen in draws {
+        assert_eq!(trace(&parse_fen(fen)).scale, 0, "{fen}");
+        assert_eq!(eval(&parse_fen(fen)), 0, "{fen}");
+    }
+}
+
+#[test]
+fn wrong_bishop_is_a_draw() {
+    // The dark-squared bishop can't drive the king out of a8
+    let wrong = parse_fen("k7/8/8/8/8/P7/P7/K1B5 w - - 0 1");
+    assert_eq!(trace(&wrong).scale, 0);
+    assert_eq!(eval(&wrong), 0);
+
+    let right = parse_fen("k7/8/8/8/8/P7/P7/KB6 w - - 0 1");
+    assert_eq!(trace(&right).scale, SCALE_NORMAL);
+    assert!(eval(&right) > DEFAULT_PARAMS.piece_values.bishop.eg);
+}
+
+#[test]
+fn opposite_bishops_are_scaled_down() {
+    let opposite = parse_fen("4k3/4b3/8/3p4/3P4/2P5/2B5/4K3 w - - 0 1");
+    let same = parse_fen("4k3/5b2/8/3p4/3P4/2P5/2B5/4K3 w - - 0 1");
+    assert!(trace(&opposite).scale < SCALE_NORMAL);
+    assert_eq!(trace(&same).scale, SCALE_NORMAL);
+}
+
+#[test]
+fn lone_king_is_driven_to_the_edge() {
+    let center = parse_fen("8/8/8/3k4/8/8/8/R3K3 w - - 0 1");
+    let edge = parse_fen("3k4/8/8/8/8/8/8/R3K3 w - - 0 1");
+    assert!(eval(&edge) > eval(&center));
+
+    // With bishop and knight, towards a corner of the bishop's color
+    let right_corner = parse_fen("7k/8/8/8/8/8/8/2BNK3 w - - 0 1");
+    let wrong_corner = parse_fen("k7/8/8/8/8/8/8/2BNK3 w - - 0 1");
+    assert!(eval(&right_corner) > eval(&wrong_corner));
+}
+
+#[test]
+fn mates_with_a_rook() {
+    let mut position = parse_fen("8/8/8/3k4/8/8/8/R3K3 w - - 0 1");
+
+    // Each move searched from scratch, far from mate only the evaluation shows progress
+    for _ in 0..60 {
+        if position.is_game_over() {
+            break;
+        }
+        let analysis = Engine::new().analyse(&position, Limits::depth(4));
+        position.play_unchecked(analysis.best_move.unwrap());
+    }
+    assert!(
+        position.is_checkmate(),
+        "{}",
+        Fen::from_position(&position, EnPassantMode::Legal)
+    );
+}
@@ -0,0 +1,197 @@
+//! Searching through the embeddable `Engine` instead of the protocol front ends.
+
+mod common;
+
+use common::parse_fen;
+use kaksic::search::eval::Handcrafted;
+use kaksic::search::skill::{Skill, MAX_ELO, MIN_ELO};
+use kaksic::search::{Limits, SearchOptions};
+use kaksic::{Engine, SearchInfo};
+use shakmaty::{Chess, Position};
+use std::time::{Duration, Instant};
+
+#[test]
+fn analyse_returns_a_legal_line() {
+    let position = Chess::default();
+    let analysis = Engine::new().analyse(&position, Limits::depth(3));
+
+    assert_eq!(analysis.depth, 3);
+    assert!(analysis.nodes > 0);
+    assert_eq!(analysis.pv.first(), analysis.best_move.as_ref());
+    assert!(position.is_legal(analysis.best_move.unwrap()));
+
+    // A move for every ply searched, no position this shallow is reached twice
+    assert_eq!(analysis.pv.len(), 3, "{:?}", analysis.pv);
+    let mut after = position;
+    for &mv in &analysis.pv {
+        assert!(after.is_legal(mv), "{:?}", analysis.pv);
+        after.play_unchecked(mv);
+    }
+}
+
+#[test]
+fn analyse_finds_mate_in_one() {
+    let position = parse_fen("7k/5Q2/6K1/8/8/8/8/8 w - - 0 1");
+    let analysis = Engine::new().analyse(&position, Limits::depth(1));
+
+    let mut after = position.clone();
+    after.play_unchecked(analysis.best_move.unwrap());
+    assert!(after.is_checkmate(), "{:?}", analysis.best_move);
+}
+
+#[test]
+fn analyse_without_legal_moves() {
+    let stalemate = parse_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1");
+    let analysis = Engine::new().analyse(&stalemate, Limits::depth(3));
+    assert_eq!(analysis.best_move, None);
+    assert!(analysis.pv.is_empty());
+}
+
+#[test]
+fn analyse_respects_time_limit() {
+    let limits = Limits::time(Duration::from_millis(200));
+    let analysis = Engine::new().analyse(&Chess::default(), limits);
+    assert!(Chess::default().is_legal(analysis.best_move.unwrap()));
+}
+
+#[test]
+fn streamed_updates_end_with_the_best_move() {
+    let mut engine = Engine::new();
+    let infos: Vec<SearchInfo> = engine
+        .analyse_iter(&Chess::default(), Limits::depth(3))
+        .collect();
+
+    let depths: Vec<u8> = infos
+        .iter()
+        .filter_map(|info| match info {
+            SearchInfo::Info { depth, .. } => Some(*depth),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(depths, [1, 2, 3]);
+    assert!(matches!(infos.last(), Some(SearchInfo::BestMove(_))));
+
+    // The engine can search again once the stream is consumed
+    let analysis = engine.analyse(&Chess::default(), Limits::depth(3));
+    assert_eq!(analysis.depth, 3);
+}
+
+#[test]
+fn finish_matches_a_blocking_search() {
+    let position = parse_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3");
+    let mut engine = Engine::new();
+
+    let mut analysing = engine.analyse_iter(&position, Limits::depth(3));
+    analysing.next();
+    let streamed = analysing.finish();
+
+    engine.clear_hash();
+    let blocking = engine.analyse(&position, Limits::depth(3));
+    assert_eq!(streamed, blocking);
+}
+
+#[test]
+fn dropping_a_stream_stops_the_search() {
+    let mut engine = Engine::new();
+    let mut analysing = engine.analyse_iter(&Chess::default(), Limits::depth(u8::MAX));
+    analysing.next();
+
+    let start = Instant::now();
+    drop(analysing);
+    assert!(start.elapsed() < Duration::from_secs(1));
+
+    // The engine has its state back
+    let analysis = engine.analyse(&Chess::default(), Limits::depth(1));
+    assert!(analysis.best_move.is_some());
+}
+
+#[test]
+fn weakened_engine_searches_less_and_still_mates() {
+    let mut engine = Engine::new();
+    engine.set_options(SearchOptions {
+        skill: Skill::new(0),
+        ..Default::default()
+    });
+
+    let analysis = engine.analyse(&Chess::default(), Limits::depth(4));
+    assert_eq!(analysis.depth, Skill::new(0).depth());
+    assert!(Chess::default().is_legal(analysis.best_move.unwrap()));
+
+    // Every other move is far worse than the mate, so it is never chosen
+    let position = parse_fen("7k/5Q2/6K1/8/8/8/8/8 w - - 0 1");
+    for _ in 0..20 {
+        let mut after = position.clone();
+        after.play_unchecked(
+            engine
+                .analyse(&position, Limits::depth(1))
+                .best_move
+                .unwrap(),
+        );
+        assert!(after.is_checkmate());
+    }
+}
+
+#[test]
+fn elo_range_spans_level_zero_to_full_strength() {
+    assert_eq!(Skill::from_elo(MIN_ELO), Skill::new(0));
+    assert!(Skill::from_elo(MAX_ELO).is_full_strength());
+    assert!(Skill::from_elo(u16::MAX).is_full_strength());
+    assert!(Skill::from_elo(MAX_ELO - 1).level() < Skill::default().level());
+}
+
+#[test]
+fn new_evaluator_forgets_stored_scores() {
+    let mut engine = Engine::new();
+    let position = Chess::default();
+    let fresh = engine.analyse(&position, Limits::depth(3)).nodes;
+
+    // The second search reuses the scores of the first
+    assert!(engine.analyse(&position, Limits::depth(3)).nodes < fresh);
+
+    engine.set_evaluator(Box::new(Handcrafted::default()));
+    assert_eq!(engine.analyse(&position, Limits::depth(3)).nodes, fresh);
+}
+
+#[test]
+fn seldepth_is_the_deepest_ply_reached() {
+    let mut engine = Engine::new();
+
+    // Full width without extensions, the search reaches exactly the iteration depth
+    let seldepths: Vec<(u8, u8)> = engine
+        .analyse_iter(&Chess::default(), Limits::depth(3))
+        .filter_map(|info| match info {
+            SearchInfo::Info {
+                depth, seldepth, ..
+            } => Some((depth, seldepth)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(seldepths, [(1, 1), (2, 2), (3, 3)]);
+}
+
+#[test]
+fn nodes_and_time_add_up_over_the_iterations() {
+    let mut engine = Engine::new();
+    let updates: Vec<(u64, Duration, Option<u16>)> = engine
+        .analyse_iter(&Chess::default(), Limits::depth(3))
+        .filter_map(|info| match info {
+            SearchInfo::Info {
+                nodes,
+                time,
+                hashfull,
+                ..
+            } => Some((nodes, time, hashfull)),
+            _ => None,
+        })
+        .collect();
+
+    assert_eq!(updates.len(), 3);
+    for pair in updates.windows(2) {
+        let ((nodes, time, hashfull), (next_nodes, next_time, next_hashfull)) = (pair[0], pair[1]);
+        assert!(next_nodes > nodes, "{updates:?}");
+        assert!(next_time >= time, "{updates:?}");
+        assert!(next_hashfull >= hashfull, "{updates:?}");
+    }
+    let (_, _, hashfull) = updates[2];
+    assert!(hashfull.is_some_and(|hashfull| hashfull <= 1000));
+}
@@ -0,0 +1,71 @@
+//! The evaluation trace accounts for the whole of `eval`.
+
+use kaksic::search::endgame::SCALE_NORMAL;
+use kaksic::search::eval::{eval, trace, Score, Term};
+use shakmaty::fen::Fen;
+use shakmaty::variant::{Variant, VariantPosition};
+use shakmaty::{CastlingMode, Color, Position};
+
+const POSITIONS: [(Variant, &str); 7] = [
+    (
+        Variant::Chess,
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    ),
+    (
+        Variant::Chess,
+        "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4",
+    ),
+    // In check, with passed and isolated pawns
+    (Variant::Chess, "4k3/1P6/8/8/8/2p5/5PPP/3rK3 w - - 0 1"),
+    // Scaled down endings
+    (Variant::Chess, "4k3/4b3/8/3p4/3P4/2P5/2B5/4K3 b - - 0 1"),
+    (Variant::Chess, "k7/8/8/8/8/8/P7/K7 w - - 0 1"),
+    (
+        Variant::KingOfTheHill,
+        "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2",
+    ),
+    (
+        Variant::ThreeCheck,
+        "rnbqkbnr/ppp2ppp/8/3pp3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 1+2 0 3",
+    ),
+];
+
+fn positions() -> impl Iterator<Item = VariantPosition> {
+    POSITIONS.into_iter().map(|(variant, fen)| {
+        let setup = fen.parse::<Fen>().unwrap().into_setup();
+        VariantPosition::from_setup(variant, setup, CastlingMode::Standard).unwrap()
+    })
+}
+
+#[test]
+fn terms_add_up_to_eval() {
+    for position in positions() {
+        let trace = trace(&position);
+
+        let mut score = Score::default();
+        for term in Term::ALL {
+            score += trace.get(term, Color::White) - trace.get(term, Color::Black);
+        }
+        assert_eq!(trace.score(), score);
+
+        let total = score.taper(trace.phase) * trace.scale / SCALE_NORMAL;
+        assert_eq!(trace.total(), total);
+        assert_eq!(
+            eval(&position),
+            position.turn().fold_wb(total, -total),
+            "{position:?}"
+        );
+    }
+}
+
+#[test]
+fn table_shows_every_term() {
+    let table = trace(&positions().next().unwrap()).to_string();
+    for term in Term::ALL {
+        assert!(table.contains(term.name()), "{table}");
+    }
+    assert!(
+        table.ends_with("Final evaluation: 0 (white side)"),
+        "{table}"
+    );
+}
@@ -0,0 +1,79 @@
+//! Labeled positions and the optimizer shared by the tuning tools.
+
+use kaksic::search::fit::{parse_line, Adam};
+use shakmaty::fen::Fen;
+use shakmaty::{Chess, EnPassantMode};
+
+const FEN: &str = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
+
+fn fen(position: &Chess) -> String {
+    Fen::from_position(position, EnPassantMode::Legal).to_string()
+}
+
+#[test]
+fn results_in_every_format() {
+    for (result, expected) in [
+        ("1-0", 1.0),
+        ("0-1", 0.0),
+        ("1/2-1/2", 0.5),
+        ("[1.0]", 1.0),
+        ("[0.0]", 0.0),
+        ("[0.5]", 0.5),
+        ("c9 \"1/2-1/2\";", 0.5),
+    ] {
+        let (position, parsed) = parse_line(&format!("{FEN} {result}")).unwrap();
+        assert_eq!(parsed, expected, "{result}");
+        assert_eq!(fen(&position), FEN, "{result}");
+    }
+}
+
+#[test]
+fn move_counters_are_optional() {
+    let (position, result) =
+        parse_line("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - [0.5]").unwrap();
+    assert_eq!(result, 0.5);
+    assert_eq!(
+        fen(&position),
+        "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 0 1"
+    );
+
+    // Only the halfmove clock
+    let (position, _) = parse_line("8/8/8/4k3/8/8/4P3/4K3 w - - 7 1-0").unwrap();
+    assert_eq!(fen(&position), "8/8/8/4k3/8/8/4P3/4K3 w - - 7 1");
+}
+
+#[test]
+fn unusable_lines_are_skipped() {
+    for line in [
+        "",
+        FEN,
+        "1-0",
+        "8/8/8 w - 1-0",
+        "not a fen at all 1-0",
+        // No kings
+        "8/8/8/8/8/8/4P3/8 w - - 0 1 1-0",
+    ] {
+        assert!(parse_line(line).is_none(), "{line}");
+    }
+}
+
+#[test]
+fn adam_steps_down_the_gradient() {
+    let mut adam = Adam::new(3, 1.0);
+    let mut params = [10.0, 10.0, 10.0];
+
+    // The first step has the size of the learning rate, whatever the size of the gradient
+    adam.step(&mut params, &[0.001, -50.0, 0.0]);
+    assert!((params[0] - 9.0).abs() < 1e-3, "{params:?}");
+    assert!((params[1] - 11.0).abs() < 1e-3, "{params:?}");
+    assert_eq!(params[2], 10.0);
+
+    // Following the gradient of (x - 3)^2 leads to its minimum
+    let mut adam = Adam::new(1, 0.1);
+    let mut x = [10.0];
+    for _ in 0..1000 {
+        let gradient = [2.0 * (x[0] - 3.0)];
+        adam.step(&mut x, &gradient);
+    }
+    assert!((x[0] - 3.0).abs() < 0.05, "{x:?}");
+}
@@ -0,0 +1,139 @@
+//! Position commands a user interface should never send, but might.
+
+use kaksic::bot::position::{setup_position, PositionError};
+use shakmaty::fen::Fen;
+use shakmaty::variant::Variant;
+use shakmaty::{CastlingMode, Position};
+use shakmaty_uci::UciMove;
+
+fn fen(text: &str) -> Option<Fen> {
+    Some(text.parse().expect("test FEN should be well formed"))
+}
+
+fn moves(text: &str) -> Vec<UciMove> {
+    text.split_whitespace()
+        .map(|mv| mv.parse().expect("test move should be well formed"))
+        .collect()
+}
+
+#[test]
+fn accepts_start_position_with_moves() {
+    let position = setup_position(
+        None,
+        &moves("e2e4 e7e5 g1f3"),
+        Variant::Chess,
+        CastlingMode::Standard,
+    )
+    .unwrap();
+    assert_eq!(position.fullmoves().get(), 2);
+}
+
+#[test]
+fn accepts_fen_with_castling_and_en_passant() {
+    let position = setup_position(
+        fen("r3k2r/8/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1"),
+        &moves("e5d6 e8c8 e1g1"),
+        Variant::Chess,
+        CastlingMode::Standard,
+    )
+    .unwrap();
+    assert!(!position.is_game_over());
+}
+
+#[test]
+fn rejects_impossible_fens() {
+    let impossible = [
+        // No pieces
+        "8/8/8/8/8/8/8/8 w - - 0 1",
+        // Missing king
+        "8/8/8/8/8/8/8/4K3 w - - 0 1",
+        // Two white kings
+        "4k3/8/8/8/8/8/8/3KK3 w - - 0 1",
+        // Pawns on the back rank
+        "P3k3/8/8/8/8/8/8/4K3 w - - 0 1",
+        // Side not to move is in check
+        "4k3/4R3/8/8/8/8/8/4K3 w - - 0 1",
+        // Castling rights without a rook
+        "4k3/8/8/8/8/8/8/4K3 w K - 0 1",
+    ];
+
+    for text in impossible {
+        let result = setup_position(fen(text), &[], Variant::Chess, CastlingMode::Standard);
+        assert!(
+            matches!(result, Err(PositionError::InvalidFen { .. })),
+            "{text} should be rejected"
+        );
+    }
+}
+
+#[test]
+fn rejects_illegal_moves() {
+    let illegal = [
+        // Pawn moving three squares
+        "e2e5",
+        // Moving the opponent's piece
+        "e7e5",
+        // Moving from an empty square
+        "e4e5",
+        // Null move
+        "0000",
+        // Castling through pieces
+        "e1g1",
+        // Dropping a piece in standard chess
+        "N@e4",
+        // Promotion of a piece that is not a pawn
+        "g1h3q",
+        // Legal move followed by an illegal one
+        "e2e4 e2e4",
+    ];
+
+    for text in illegal {
+        let result = setup_position(None, &moves(text), Variant::Chess, CastlingMode::Standard);
+        assert!(
+            matches!(result, Err(PositionError::IllegalMove { .. })),
+            "{text} should be rejected"
+        );
+    }
+}
+
+#[test]
+fn rejects_moves_after_the_game_is_over() {
+    let result = setup_position(
+        None,
+        &moves("f2f3 e7e5 g2g4 d8h4 a2a3"),
+        Variant::Chess,
+        CastlingMode::Standard,
+    );
+    match result {
+        Err(PositionError::IllegalMove { ply, .. }) => assert_eq!(ply, 4),
+        other => panic!("expected an illegal move, got {other:?}"),
+    }
+}
+
+#[test]
+fn reports_the_offending_move() {
+    let err = setup_position(
+        None,
+        &moves("e2e4 e7e5 e1e3"),
+        Variant::Chess,
+        CastlingMode::Standard,
+    )
+    .unwrap_err();
+    let message = err.to_string();
+    assert!(message.contains("e1e3"), "{message}");
+    assert!(message.contains("move 3"), "{message}");
+}
+
+#[test]
+fn long_games_do_not_overflow() {
+    // Shuffle knights back and forth well past any move counter limits of the search
+    let shuffle = "g1f3 g8f6 f3g1 f6g8 ".repeat(200);
+    let position = setup_position(
+        None,
+        &moves(&shuffle),
+        Variant::Chess,
+        CastlingMode::Standard,
+    )
+    .unwrap();
+    assert!(position.halfmoves() >= 800);
+}
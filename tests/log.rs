@@ -0,0 +1,108 @@
+//! Runtime log written by the background logger.
+
+use kaksic::bot::log::{FileLogger, Format, Level, Logger};
+use std::fs;
+use std::path::PathBuf;
+
+/// Fresh path in the temporary directory
+fn log_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("kaksic-{}-{name}.log", std::process::id()));
+    let _ = fs::remove_file(&path);
+    let _ = fs::remove_file(path.with_extension("log.1"));
+    path
+}
+
+#[test]
+fn lines_have_millisecond_timestamps_and_levels() {
+    let path = log_path("text");
+    let logger = FileLogger::new(path.to_str().unwrap());
+    logger.log(Level::Info, " IN: 'uci'");
+    logger.log(Level::Warn, "ERR: 'unknown command: foo'");
+    drop(logger);
+
+    let log = fs::read_to_string(&path).unwrap();
+    let lines: Vec<&str> = log.lines().collect();
+    assert_eq!(lines.len(), 2);
+
+    // `2026-01-31 12:34:56.789 Info   IN: 'uci'`
+    let (date, rest) = lines[0].split_once(' ').unwrap();
+    let (time, rest) = rest.split_once(' ').unwrap();
+    assert_eq!(date.len(), 10);
+    assert_eq!(time.len(), 12);
+    assert_eq!(&time[8..9], ".");
+    assert_eq!(rest, "Info   IN: 'uci'");
+    assert!(lines[1].ends_with("Warn  ERR: 'unknown command: foo'"));
+}
+
+#[test]
+fn less_important_lines_are_dropped() {
+    let path = log_path("level");
+    let mut logger = FileLogger::new(path.to_str().unwrap());
+    logger.log(Level::Debug, "hidden");
+    logger.set_level(Level::Debug);
+    logger.log(Level::Debug, "shown");
+    logger.set_level(Level::Error);
+    logger.log(Level::Warn, "hidden");
+    logger.log(Level::Error, "shown");
+    drop(logger);
+
+    let log = fs::read_to_string(&path).unwrap();
+    assert_eq!(log.matches("shown").count(), 2);
+    assert!(!log.contains("hidden"));
+}
+
+#[test]
+fn json_lines_are_escaped() {
+    let path = log_path("json");
+    let mut logger = FileLogger::new(path.to_str().unwrap());
+    logger.set_format(Format::Json);
+    logger.log(Level::Info, "OUT: 'say \"hi\" \\ bye'");
+    drop(logger);
+
+    let log = fs::read_to_string(&path).unwrap();
+    let line = log.trim_end();
+    assert!(line.starts_with("{\"time\":\""), "{line}");
+    assert!(
+        line.ends_with(r#","level":"info","message":"OUT: 'say \"hi\" \\ bye'"}"#),
+        "{line}"
+    );
+}
+
+#[test]
+fn path_can_be_changed_or_disabled() {
+    let first = log_path("first");
+    let second = log_path("second");
+    let mut logger = FileLogger::new(first.to_str().unwrap());
+    logger.log(Level::Info, "one");
+    logger.set_path(second.to_str().unwrap());
+    logger.log(Level::Info, "two");
+    logger.set_path("");
+    logger.log(Level::Info, "three");
+    drop(logger);
+
+    assert!(fs::read_to_string(&first).unwrap().contains("one"));
+    let log = fs::read_to_string(&second).unwrap();
+    assert!(log.contains("two") && !log.contains("three"));
+}
+
+#[test]
+fn unwritable_path_disables_logging() {
+    let logger = FileLogger::new("/nonexistent/directory/engine.log");
+    logger.log(Level::Error, "lost");
+}
+
+#[test]
+fn large_files_are_rotated() {
+    let path = log_path("rotate");
+    let logger = FileLogger::new(path.to_str().unwrap());
+    let line = "x".repeat(1000);
+    for _ in 0..11_000 {
+        logger.log(Level::Info, &line);
+    }
+    drop(logger);
+
+    let rotated = fs::metadata(path.with_extension("log.1")).unwrap().len();
+    let current = fs::metadata(&path).unwrap().len();
+    assert!(rotated >= 10 * 1024 * 1024, "{rotated}");
+    assert!(current < rotated, "{current}");
+}
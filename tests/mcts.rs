@@ -0,0 +1,137 @@
+//! Monte Carlo tree search, through the embeddable `Engine`.
+
+mod common;
+
+use common::parse_fen;
+use kaksic::search::mcts::Mcts;
+use kaksic::search::skill::Skill;
+use kaksic::search::{Limits, SearchOptions};
+use kaksic::{Engine, SearchInfo};
+use shakmaty::{Chess, Move, Position};
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+fn mcts(options: SearchOptions) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_algorithm(Box::new(Mcts));
+    engine.set_options(options);
+    engine
+}
+
+/// First move and score of every line in the last report
+fn last_lines(engine: &mut Engine, position: &Chess, limits: Limits) -> Vec<(Move, i32)> {
+    let mut lines = Vec::new();
+    for info in engine.analyse_iter(position, limits) {
+        if let SearchInfo::Info {
+            multipv, pv, score, ..
+        } = info
+        {
+            if multipv == 1 {
+                lines.clear();
+            }
+            lines.push((pv[0], score));
+        }
+    }
+    lines
+}
+
+#[test]
+fn finds_mate_in_one() {
+    // Every queen move keeps a winning score, a few of them stalemate
+    let position = parse_fen("7k/5Q2/6K1/8/8/8/8/8 w - - 0 1");
+    let analysis = mcts(SearchOptions::default()).analyse(&position, Limits::depth(3));
+
+    let mut after = position.clone();
+    after.play_unchecked(analysis.best_move.unwrap());
+    assert!(after.is_checkmate(), "{:?}", analysis.best_move);
+    assert_eq!(analysis.pv, [analysis.best_move.unwrap()]);
+}
+
+#[test]
+fn returns_a_legal_move_and_line() {
+    let position = parse_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3");
+    let analysis = mcts(SearchOptions::default()).analyse(&position, Limits::depth(2));
+
+    assert!(analysis.depth >= 2, "{analysis:?}");
+    assert_eq!(analysis.pv.first(), analysis.best_move.as_ref());
+    let mut after = position.clone();
+    for &mv in &analysis.pv {
+        assert!(after.is_legal(mv), "{:?}", analysis.pv);
+        after.play_unchecked(mv);
+    }
+}
+
+#[test]
+fn scores_checkmate_and_stalemate() {
+    let position = parse_fen("7k/5Q2/6K1/8/8/8/8/8 w - - 0 1");
+    let mut engine = mcts(SearchOptions {
+        multi_pv: u8::MAX,
+        ..Default::default()
+    });
+    let lines = last_lines(&mut engine, &position, Limits::depth(3));
+
+    let best = lines.iter().map(|&(_, score)| score).max().unwrap();
+    let mut stalemates = 0;
+    for (mv, score) in lines {
+        let mut after = position.clone();
+        after.play_unchecked(mv);
+        if after.is_checkmate() {
+            assert_eq!(score, best, "{mv}");
+        } else if after.is_stalemate() {
+            assert_eq!(score, 0, "{mv}");
+            stalemates += 1;
+        } else {
+            assert!(score > 500 && score < best, "{mv} {score}");
+        }
+    }
+    assert!(stalemates > 0);
+}
+
+#[test]
+fn weakened_search_stays_within_its_iterations() {
+    // Reaching the depth of the skill level takes far more iterations than it allows
+    let skill = Skill::new(10);
+    let position = parse_fen("7k/8/5K2/8/8/1Q6/8/8 w - - 0 1");
+    let analysis = mcts(SearchOptions {
+        skill,
+        ..Default::default()
+    })
+    .analyse(&position, Limits::depth(10));
+
+    assert_eq!(analysis.nodes, skill.nodes());
+    assert!(analysis.depth < skill.depth(), "{analysis:?}");
+    assert!(position.is_legal(analysis.best_move.unwrap()));
+}
+
+#[test]
+fn stops_when_told() {
+    let mut engine = mcts(SearchOptions::default());
+    let limits = Limits::depth(u8::MAX);
+    let stop = limits.stop.clone();
+
+    let analysing = engine.analyse_iter(&Chess::default(), limits);
+    std::thread::sleep(Duration::from_millis(200));
+    let start = Instant::now();
+    stop.store(true, Ordering::Relaxed);
+    let analysis = analysing.finish();
+
+    assert!(
+        start.elapsed() < Duration::from_millis(500),
+        "{:?}",
+        start.elapsed()
+    );
+    assert!(Chess::default().is_legal(analysis.best_move.unwrap()));
+    assert!(analysis.nodes > 0);
+}
+
+#[test]
+fn stopped_before_starting_still_moves() {
+    let limits = Limits::depth(u8::MAX);
+    limits.stop.store(true, Ordering::Relaxed);
+    let analysis = mcts(SearchOptions::default()).analyse(&Chess::default(), limits);
+
+    // The one iteration only expands the root, so there is no line to report
+    assert_eq!(analysis.nodes, 0);
+    assert_eq!(analysis.pv.len(), 1);
+    assert!(Chess::default().is_legal(analysis.best_move.unwrap()));
+}
@@ -0,0 +1,181 @@
+//! Incremental accumulator updates and network file parsing.
+
+use kaksic::search::nnue::{Network, NetworkError, NnueState};
+use shakmaty::fen::Fen;
+use shakmaty::uci::UciMove;
+use shakmaty::{CastlingMode, Chess, Position};
+use std::sync::Arc;
+
+const INPUTS: usize = 768;
+
+/// Network file with small pseudo-random weights, the same every time
+fn network_bytes(hidden: usize) -> Vec<u8> {
+    let mut state: u32 = 0x9e37_79b9;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        (state % 129) as i16 - 64
+    };
+
+    let mut bytes = b"KNUE".to_vec();
+    bytes.extend((hidden as u32).to_le_bytes());
+    for _ in 0..INPUTS * hidden + hidden + 2 * hidden {
+        bytes.extend(next().to_le_bytes());
+    }
+    bytes.extend(1234i32.to_le_bytes());
+    bytes
+}
+
+fn network(hidden: usize) -> Arc<Network> {
+    Arc::new(Network::from_bytes(&network_bytes(hidden)).unwrap())
+}
+
+fn parse_fen(fen: &str, mode: CastlingMode) -> Chess {
+    fen.parse::<Fen>().unwrap().into_position(mode).unwrap()
+}
+
+/// Play `moves` pushing each onto the accumulator, and compare with a refresh after every one
+fn assert_incremental(fen: &str, mode: CastlingMode, moves: &[&str]) {
+    let network = network(32);
+    let mut position = parse_fen(fen, mode);
+    let mut incremental = NnueState::new(network.clone());
+    incremental.refresh(position.board());
+    let mut evals = vec![(position.turn(), incremental.evaluate(position.turn()))];
+
+    for &uci in moves {
+        let mv = uci.parse::<UciMove>().unwrap().to_move(&position).unwrap();
+        incremental.push(&position, mv);
+        position.play_unchecked(mv);
+
+        let mut refreshed = NnueState::new(network.clone());
+        refreshed.refresh(position.board());
+        let eval = incremental.evaluate(position.turn());
+        assert_eq!(eval, refreshed.evaluate(position.turn()), "after {uci}");
+        evals.push((position.turn(), eval));
+    }
+
+    // Taking the moves back restores each earlier evaluation
+    evals.pop();
+    while let Some((turn, eval)) = evals.pop() {
+        incremental.pop();
+        assert_eq!(incremental.evaluate(turn), eval);
+    }
+}
+
+#[test]
+fn captures_match_a_refresh() {
+    assert_incremental(
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        CastlingMode::Standard,
+        &["e2e4", "d7d5", "e4d5", "d8d5", "b1c3", "d5a2", "a1a2"],
+    );
+}
+
+#[test]
+fn en_passant_matches_a_refresh() {
+    assert_incremental(
+        "rnbqkbnr/ppp1pppp/8/8/3p4/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        CastlingMode::Standard,
+        &[
+            "e2e4", "d4e3", "f2e3", "c7c5", "e3e4", "c5c4", "b2b4", "c4b3",
+        ],
+    );
+}
+
+#[test]
+fn castling_matches_a_refresh() {
+    assert_incremental(
+        "r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R w KQkq - 0 1",
+        CastlingMode::Standard,
+        &["e1g1", "e8c8"],
+    );
+    // Chess960 castling, with the king moving onto the rook's square
+    assert_incremental(
+        "1r2k1r1/pppppppp/8/8/8/8/PPPPPPPP/1R2K1R1 w GBgb - 0 1",
+        CastlingMode::Chess960,
+        &["e1b1", "e8g8"],
+    );
+}
+
+#[test]
+fn promotions_match_a_refresh() {
+    assert_incremental(
+        "1n6/P4k2/8/8/8/8/6p1/4K2R w K - 0 1",
+        CastlingMode::Standard,
+        &["a7b8q", "g2h1n", "b8e5", "f7g6"],
+    );
+    assert_incremental(
+        "8/P3k3/8/8/8/8/6p1/4K3 w - - 0 1",
+        CastlingMode::Standard,
+        &["a7a8r", "g2g1b"],
+    );
+}
+
+#[test]
+fn large_networks_do_not_overflow() {
+    // Every hidden value at the clipping limit, every output weight at its largest
+    let hidden = 512;
+    let mut bytes = b"KNUE".to_vec();
+    bytes.extend((hidden as u32).to_le_bytes());
+    bytes.extend(vec![0u8; 2 * INPUTS * hidden]);
+    for _ in 0..hidden {
+        bytes.extend(255i16.to_le_bytes());
+    }
+    for _ in 0..2 * hidden {
+        bytes.extend(i16::MAX.to_le_bytes());
+    }
+    bytes.extend(0i32.to_le_bytes());
+
+    let mut state = NnueState::new(Arc::new(Network::from_bytes(&bytes).unwrap()));
+    let position = Chess::default();
+    state.refresh(position.board());
+    let eval = state.evaluate(position.turn());
+    assert!(eval > 0, "{eval}");
+}
+
+#[test]
+fn rejects_bad_headers() {
+    assert!(matches!(
+        Network::from_bytes(b""),
+        Err(NetworkError::BadMagic)
+    ));
+    assert!(matches!(
+        Network::from_bytes(b"KNUE"),
+        Err(NetworkError::BadMagic)
+    ));
+
+    let mut bytes = network_bytes(4);
+    bytes[..4].copy_from_slice(b"NNUE");
+    assert!(matches!(
+        Network::from_bytes(&bytes),
+        Err(NetworkError::BadMagic)
+    ));
+}
+
+#[test]
+fn rejects_bad_sizes() {
+    let bytes = network_bytes(4);
+    let expected = bytes.len();
+
+    let truncated = &bytes[..expected - 1];
+    assert!(matches!(
+        Network::from_bytes(truncated),
+        Err(NetworkError::BadSize { actual, .. }) if actual == expected - 1
+    ));
+
+    let mut padded = bytes.clone();
+    padded.push(0);
+    assert!(matches!(
+        Network::from_bytes(&padded),
+        Err(NetworkError::BadSize { .. })
+    ));
+
+    // A hidden size that doesn't match the weights that follow
+    let mut wrong_hidden = bytes;
+    wrong_hidden[4..8].copy_from_slice(&5u32.to_le_bytes());
+    assert!(matches!(
+        Network::from_bytes(&wrong_hidden),
+        Err(NetworkError::BadSize { actual, .. }) if actual == expected
+    ));
+}
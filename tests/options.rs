@@ -0,0 +1,91 @@
+//! Parsing of option values sent with `setoption`.
+
+use kaksic::bot::options::{self, OptionType, OptionValue, OPTIONS};
+use kaksic::search::skill::MAX_ELO;
+
+fn parse(name: &str, value: &str) -> Result<OptionValue, String> {
+    options::find(name).unwrap().parse(Some(value))
+}
+
+#[test]
+fn finds_options_ignoring_case() {
+    assert_eq!(options::find("multipv").unwrap().name, options::MULTI_PV);
+    assert_eq!(options::find(" Hash ").unwrap().name, options::HASH);
+    assert!(options::find("Ponder").is_none());
+}
+
+#[test]
+fn threads_is_fixed_at_one() {
+    let threads = options::find("threads").unwrap();
+    assert_eq!(threads.name, options::THREADS);
+    assert!(matches!(
+        threads.kind,
+        OptionType::Spin {
+            default: 1,
+            min: 1,
+            max: 1
+        }
+    ));
+    assert!(OPTIONS
+        .iter()
+        .any(|option| option.to_string() == "option name Threads type spin default 1 min 1 max 1"));
+
+    // Any other count is clamped to the one thread there is
+    assert_eq!(parse(options::THREADS, "1"), Ok(OptionValue::Spin(1)));
+    assert_eq!(parse(options::THREADS, "8"), Ok(OptionValue::Spin(1)));
+    assert_eq!(parse(options::THREADS, "0"), Ok(OptionValue::Spin(1)));
+    assert!(parse(options::THREADS, "many").is_err());
+}
+
+#[test]
+fn parses_each_kind() {
+    assert_eq!(parse(options::HASH, " 64 "), Ok(OptionValue::Spin(64)));
+    assert_eq!(
+        parse(options::USE_NNUE, "FALSE"),
+        Ok(OptionValue::Check(false))
+    );
+    assert_eq!(
+        parse(options::SEARCH_ALGORITHM, "mcts"),
+        Ok(OptionValue::Combo("MCTS"))
+    );
+    assert_eq!(
+        parse(options::BOOK_FILE, "book.bin"),
+        Ok(OptionValue::String("book.bin".to_string()))
+    );
+    assert_eq!(
+        parse(options::BOOK_FILE, "<empty>"),
+        Ok(OptionValue::String(String::new()))
+    );
+    assert_eq!(parse(options::CLEAR_HASH, ""), Ok(OptionValue::Button));
+}
+
+#[test]
+fn clamps_numbers_to_the_range() {
+    assert_eq!(parse(options::HASH, "0"), Ok(OptionValue::Spin(1)));
+    assert_eq!(parse(options::HASH, "100000"), Ok(OptionValue::Spin(1024)));
+    assert_eq!(parse(options::MULTI_PV, "-3"), Ok(OptionValue::Spin(1)));
+    assert_eq!(
+        parse(options::ELO, "9999"),
+        Ok(OptionValue::Spin(MAX_ELO as i64))
+    );
+}
+
+#[test]
+fn rejects_values_of_the_wrong_kind() {
+    assert_eq!(
+        parse(options::HASH, "lots"),
+        Err("Hash must be a number from 1 to 1024, got 'lots'".to_string())
+    );
+    assert_eq!(
+        parse(options::USE_NNUE, "yes"),
+        Err("Use NNUE must be true or false, got 'yes'".to_string())
+    );
+    assert_eq!(
+        parse(options::BOOK_SELECTION, "Random"),
+        Err("BookSelection must be one of Weighted, Best, got 'Random'".to_string())
+    );
+    assert!(options::find(options::MULTI_PV)
+        .unwrap()
+        .parse(None)
+        .is_err());
+}
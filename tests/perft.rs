@@ -0,0 +1,117 @@
+//! Leaf node counts of well known positions, from https://www.chessprogramming.org/Perft_Results
+
+mod common;
+
+use common::parse_fen;
+use kaksic::bot::position::setup_position;
+use kaksic::search::perft::{divide, perft};
+use shakmaty::variant::{Variant, VariantPosition};
+use shakmaty::{CastlingMode, Chess};
+use shakmaty_uci::UciMove;
+
+/// Positions with node counts for increasing depths, starting at depth 1
+const SUITE: &[(&str, &[u64])] = &[
+    (
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        &[20, 400, 8902, 197281],
+    ),
+    (
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        &[48, 2039, 97862],
+    ),
+    (
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        &[14, 191, 2812, 43238],
+    ),
+    (
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        &[6, 264, 9467],
+    ),
+    (
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        &[44, 1486, 62379],
+    ),
+    (
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        &[46, 2079, 89890],
+    ),
+];
+
+fn moves(text: &str) -> Vec<UciMove> {
+    text.split_whitespace()
+        .map(|mv| mv.parse().unwrap())
+        .collect()
+}
+
+#[test]
+fn reference_positions() {
+    for &(fen, counts) in SUITE {
+        let position = parse_fen(fen);
+        for (depth, &expected) in (1..).zip(counts) {
+            assert_eq!(perft(&position, depth), expected, "{fen} at depth {depth}");
+        }
+    }
+}
+
+#[test]
+fn divide_adds_up_to_perft() {
+    let position = parse_fen(SUITE[1].0);
+    let result = divide(&position, 3, CastlingMode::Standard);
+    assert_eq!(result.moves.len(), 48);
+    assert_eq!(result.nodes(), 97862);
+}
+
+#[test]
+fn divide_of_start_position() {
+    let result = divide(&Chess::default(), 3, CastlingMode::Standard);
+    let count = |uci: &str| {
+        result
+            .moves
+            .iter()
+            .find(|(mv, _)| UciMove::from_move(*mv, CastlingMode::Standard).to_string() == uci)
+            .map(|&(_, nodes)| nodes)
+    };
+    assert_eq!(count("e2e4"), Some(600));
+    assert_eq!(count("g1f3"), Some(440));
+    assert_eq!(count("a2a3"), Some(380));
+}
+
+#[test]
+fn divide_at_depth_zero_is_empty() {
+    let result = divide(&Chess::default(), 0, CastlingMode::Standard);
+    assert!(result.moves.is_empty());
+    assert_eq!(result.nodes(), 0);
+    assert_eq!(result.to_string(), "\nNodes searched: 0");
+
+    // Depth 1 counts each move once
+    let result = divide(&Chess::default(), 1, CastlingMode::Standard);
+    assert_eq!(result.moves.len(), 20);
+    assert!(result.moves.iter().all(|&(_, nodes)| nodes == 1));
+}
+
+#[test]
+fn replayed_moves_reach_the_same_position() {
+    // The same positions reached through a FEN and through the moves of a `position` command
+    let cases = [
+        (
+            "e2e4 e7e5 g1f3 b8c6 f1c4 g8f6 e1g1",
+            "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQ1RK1 b kq - 5 4",
+        ),
+        (
+            "e2e4 a7a6 e4e5 d7d5",
+            "rnbqkbnr/1pp1pppp/p7/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3",
+        ),
+        (
+            "a2a4 b7b5 a4b5 a7a6 b5a6 c8b7 a6b7 g8f6 b7a8q",
+            "Qn1qkb1r/2pppppp/5n2/8/8/8/1PPPPPPP/RNBQKBNR b KQk - 0 5",
+        ),
+    ];
+
+    for (uci, fen) in cases {
+        let replayed =
+            setup_position(None, &moves(uci), Variant::Chess, CastlingMode::Standard).unwrap();
+        let expected = parse_fen(fen);
+        assert_eq!(replayed, VariantPosition::from(expected.clone()), "{uci}");
+        assert_eq!(perft(&replayed, 3), perft(&expected, 3), "{uci}");
+    }
+}
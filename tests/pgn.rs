@@ -0,0 +1,97 @@
+//! Reading finished games for the book and tuning tools.
+
+use kaksic::bot::pgn::{Game, Games};
+use shakmaty::fen::Fen;
+use shakmaty::{Chess, Color, EnPassantMode, Position};
+
+const PGN: &str = r#"[Event "Finished"]
+[Result "1-0"]
+
+1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0
+
+[Event "Unfinished"]
+[Result "*"]
+
+1. d4 d5 *
+
+[Event "Other rules"]
+[Variant "Crazyhouse"]
+[Result "0-1"]
+
+1. e4 d5 2. exd5 Qxd5 0-1
+
+[Event "Illegal move"]
+[Result "1/2-1/2"]
+
+1. e4 e4 1/2-1/2
+
+[Event "Unreadable start"]
+[SetUp "1"]
+[FEN "8/8/8/8/8/8/8/8 w - - 0 1"]
+[Result "1/2-1/2"]
+
+1/2-1/2
+
+[Event "From a position"]
+[Variant "From Position"]
+[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 b - - 0 1"]
+[Result "1/2-1/2"]
+
+1... Kd7 2. Kd2 1/2-1/2
+"#;
+
+fn read(pgn: &str) -> (Vec<Game>, usize) {
+    let mut reader = Games::new(pgn.as_bytes());
+    let games = reader.by_ref().collect::<Result<_, _>>().unwrap();
+    (games, reader.skipped())
+}
+
+fn fen(position: &impl Position) -> String {
+    Fen::from_position(position, EnPassantMode::Legal).to_string()
+}
+
+#[test]
+fn only_finished_standard_games_are_read() {
+    let (games, skipped) = read(PGN);
+    assert_eq!(games.len(), 2);
+    assert_eq!(skipped, 4);
+
+    assert_eq!(games[0].winner, Some(Color::White));
+    assert_eq!(games[0].white_result(), 1.0);
+    assert_eq!(games[0].moves.len(), 7);
+    assert_eq!(games[1].winner, None);
+    assert_eq!(games[1].white_result(), 0.5);
+}
+
+#[test]
+fn games_replay_from_their_start() {
+    let (games, _) = read(PGN);
+
+    let positions: Vec<String> = games[0]
+        .positions()
+        .map(|position| fen(&position))
+        .collect();
+    assert_eq!(positions.len(), 8);
+    assert_eq!(positions[0], fen(&Chess::default()));
+    assert!(games[0].positions().last().unwrap().is_checkmate());
+
+    let replay: Vec<(String, String)> = games[1]
+        .replay()
+        .map(|(position, mv)| (fen(&position), mv.to_string()))
+        .collect();
+    assert_eq!(
+        replay,
+        [
+            ("4k3/8/8/8/8/8/4P3/4K3 b - - 0 1".into(), "Ke8-d7".into()),
+            ("8/3k4/8/8/8/8/4P3/4K3 w - - 1 2".into(), "Ke1-d2".into()),
+        ]
+    );
+}
+
+#[test]
+fn empty_input() {
+    let (games, skipped) = read("");
+    assert!(games.is_empty());
+    assert_eq!(skipped, 0);
+}
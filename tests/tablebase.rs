@@ -0,0 +1,161 @@
+//! Syzygy probing against the small tables in `tests/fixtures/syzygy`, taken from the
+//! shakmaty-syzygy 0.1.0 package.
+
+mod common;
+
+use common::parse_fen;
+use kaksic::search::tablebase::{Tablebases, TB_WIN_SCORE};
+use kaksic::search::Limits;
+use kaksic::{Engine, SearchInfo};
+use shakmaty::uci::UciMove;
+use shakmaty::{Chess, Position};
+use shakmaty_syzygy::Tablebase;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/syzygy");
+
+fn tables() -> Tablebases {
+    Tablebases::open(FIXTURES).unwrap()
+}
+
+fn uci(moves: &[shakmaty::Move]) -> Vec<String> {
+    moves
+        .iter()
+        .map(|&mv| UciMove::from_standard(mv).to_string())
+        .collect()
+}
+
+#[test]
+fn opens_the_fixture_tables() {
+    assert_eq!(tables().max_pieces(), 3);
+    assert!(Tablebases::open("/nonexistent/syzygy").is_err());
+}
+
+#[test]
+fn wdl_from_the_side_to_move() {
+    let tables = tables();
+
+    let krk = "8/8/8/3k4/8/8/8/R3K3";
+    assert_eq!(
+        tables.probe_wdl(&parse_fen(&format!("{krk} w - - 0 1"))),
+        Some(TB_WIN_SCORE)
+    );
+    assert_eq!(
+        tables.probe_wdl(&parse_fen(&format!("{krk} b - - 0 1"))),
+        Some(-TB_WIN_SCORE)
+    );
+    // Rook pawn with the defending king in the corner
+    assert_eq!(
+        tables.probe_wdl(&parse_fen("k7/8/8/8/8/8/P7/K7 w - - 0 1")),
+        Some(0)
+    );
+
+    // Only right after a capture or pawn move, and only for covered material
+    assert_eq!(
+        tables.probe_wdl(&parse_fen(&format!("{krk} w - - 3 10"))),
+        None
+    );
+    assert_eq!(
+        tables.probe_wdl(&parse_fen("8/8/8/3k4/8/8/P7/R3K3 w - - 0 1")),
+        None
+    );
+}
+
+#[test]
+fn root_moves_keep_the_best_result() {
+    let tables = tables();
+
+    // Only the mate is kept
+    let (moves, probes) = tables
+        .root_moves(&parse_fen("k7/8/1K6/8/8/8/8/7R w - - 0 1"))
+        .unwrap();
+    assert_eq!(uci(&moves), ["h1h8"]);
+    assert!(probes > 0);
+
+    // Of the winning moves only the fastest are kept, leaving the rook to be taken draws
+    let position = parse_fen("8/8/8/8/8/8/2k5/K2R4 w - - 0 1");
+    let (moves, _) = tables.root_moves(&position).unwrap();
+    let mut syzygy = Tablebase::<Chess>::new();
+    syzygy.add_directory(FIXTURES).unwrap();
+    let dtz = |mv| {
+        let after = position.clone().play(mv).unwrap();
+        syzygy.probe_dtz(&after).unwrap().ignore_rounding().0
+    };
+    let fastest = position
+        .legal_moves()
+        .into_iter()
+        .map(dtz)
+        .filter(|&dtz| dtz < 0)
+        .max()
+        .unwrap();
+    let expected: Vec<_> = position
+        .legal_moves()
+        .into_iter()
+        .filter(|&mv| dtz(mv) == fastest)
+        .collect();
+    assert_eq!(uci(&moves), uci(&expected));
+    assert!(!uci(&moves).contains(&"a1a2".to_string()));
+
+    // A drawn position keeps every move, none of them loses
+    let position = parse_fen("k7/8/8/8/8/8/P7/K7 w - - 0 1");
+    let (moves, _) = tables.root_moves(&position).unwrap();
+    assert_eq!(moves.len(), position.legal_moves().len());
+}
+
+#[test]
+fn search_reports_probes() {
+    let mut engine = Engine::new();
+    engine.set_tablebases(Some(tables()));
+
+    let position = parse_fen("k7/8/1K6/8/8/8/8/7R w - - 0 1");
+    let infos: Vec<SearchInfo> = engine.analyse_iter(&position, Limits::depth(2)).collect();
+
+    let tbhits: Vec<u64> = infos
+        .iter()
+        .filter_map(|info| match info {
+            SearchInfo::Info { tbhits, .. } => Some(*tbhits),
+            _ => None,
+        })
+        .collect();
+    // One DTZ probe per root move that isn't mate, the kept mate isn't probed further
+    let root_probes = position.legal_moves().len() as u64 - 1;
+    assert_eq!(tbhits, [root_probes, root_probes]);
+    assert!(matches!(
+        infos.last(),
+        Some(SearchInfo::BestMove(Some(mv))) if UciMove::from_standard(*mv).to_string() == "h1h8"
+    ));
+}
+
+#[test]
+fn search_counts_each_probe_once() {
+    let mut engine = Engine::new();
+    engine.set_tablebases(Some(tables()));
+
+    // Too many pieces for the tables, only taking the rook leads into them
+    let position = parse_fen("k7/8/8/8/8/8/r7/R3K3 w - - 0 1");
+    let tbhits = engine
+        .analyse_iter(&position, Limits::depth(1))
+        .find_map(|info| match info {
+            SearchInfo::Info { tbhits, .. } => Some(tbhits),
+            _ => None,
+        });
+    assert_eq!(tbhits, Some(1));
+}
+
+#[test]
+fn seldepth_ends_at_the_tables() {
+    let mut engine = Engine::new();
+    engine.set_tablebases(Some(tables()));
+
+    // Only pawn moves, after which the tables end the search one ply from the root
+    let position = parse_fen("8/8/8/8/8/8/P1k5/K7 w - - 0 1");
+    let depths: Vec<(u8, u8)> = engine
+        .analyse_iter(&position, Limits::depth(3))
+        .filter_map(|info| match info {
+            SearchInfo::Info {
+                depth, seldepth, ..
+            } => Some((depth, seldepth)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(depths, [(1, 1), (2, 1), (3, 1)]);
+}
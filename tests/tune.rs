@@ -0,0 +1,73 @@
+//! Texel tuning of the evaluation weights.
+
+mod common;
+
+use common::parse_fen;
+use kaksic::search::eval::{self, DEFAULT_PARAMS};
+use kaksic::search::fit::Adam;
+use kaksic::search::tune::{error, find_scaling_constant, gradient, Entry};
+use shakmaty::Position;
+
+fn default_weights() -> Vec<f64> {
+    let mut params = DEFAULT_PARAMS;
+    params
+        .weights_mut()
+        .into_iter()
+        .map(|w| *w as f64)
+        .collect()
+}
+
+fn entries() -> Vec<Entry> {
+    [
+        (
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+            0.5,
+        ),
+        (
+            "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+            0.5,
+        ),
+        ("4k3/8/8/8/8/8/PPPP4/R3K3 w - - 0 1", 1.0),
+        ("r3k3/pppp4/8/8/8/8/8/4K3 w - - 0 1", 0.0),
+        ("3qk3/8/8/8/3N4/8/4PP2/4K3 w - - 0 1", 0.0),
+        ("4k3/8/4K3/4P3/8/8/8/8 b - - 0 1", 1.0),
+    ]
+    .into_iter()
+    .map(|(fen, result)| Entry::new(&parse_fen(fen), result))
+    .collect()
+}
+
+#[test]
+fn entries_evaluate_like_the_engine() {
+    // Including the endgame knowledge, which no weight controls
+    let weights = default_weights();
+    for fen in [
+        "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+        "4k3/8/4K3/4P3/8/8/8/8 b - - 0 1",
+        "8/8/8/3k4/8/8/8/R3K3 w - - 0 1",
+    ] {
+        let position = parse_fen(fen);
+        let white = position.turn().fold_wb(1, -1) * eval::eval(&position);
+        let tuned = Entry::new(&position, 0.5).evaluate(&weights);
+        assert!(
+            (tuned - white as f64).abs() <= 2.0,
+            "{fen}: {tuned} {white}"
+        );
+    }
+}
+
+#[test]
+fn tuning_steps_reduce_the_error() {
+    let entries = entries();
+    let mut weights = default_weights();
+    let k = find_scaling_constant(&entries, &weights);
+
+    let initial = error(&entries, &weights, k);
+    let mut adam = Adam::new(weights.len(), 0.1);
+    for _ in 0..10 {
+        let gradient = gradient(&entries, &weights, k);
+        adam.step(&mut weights, &gradient);
+    }
+    let tuned = error(&entries, &weights, k);
+    assert!(tuned < initial, "{tuned} {initial}");
+}
@@ -0,0 +1,511 @@
+//! Scripted UCI sessions against the controller and searcher, with output captured line by line.
+
+mod common;
+
+use common::{finish, parse_fen, Session, REPLY_TIMEOUT, SEARCH_TIMEOUT};
+use crossbeam_channel::unbounded;
+use kaksic::bot::book;
+use kaksic::bot::controller::Controller;
+use kaksic::bot::input::InputListener;
+use kaksic::bot::log::FileLogger;
+use kaksic::bot::options::OPTIONS;
+use kaksic::search::eval;
+use shakmaty::zobrist::Zobrist64;
+use shakmaty::{Chess, EnPassantMode, Position};
+use shakmaty_uci::UciMove;
+use std::thread;
+use std::time::{Duration, Instant};
+use std::{env, fs};
+
+impl Session<InputListener> {
+    fn start() -> Self {
+        let (input_tx, input_rx) = unbounded();
+        Session::spawn(InputListener::new(input_tx), |cmd_tx, info_rx, lines| {
+            Controller::new(input_rx, cmd_tx, info_rx, lines, FileLogger::new("")).run()
+        })
+    }
+
+    /// Collect info lines until the best move, which is returned last
+    fn search_output(&self, timeout: Duration) -> Vec<String> {
+        let deadline = Instant::now() + timeout;
+        let mut lines = Vec::new();
+        loop {
+            let line = self.next_line(deadline.saturating_duration_since(Instant::now()));
+            let done = line.starts_with("bestmove ");
+            assert!(done || line.starts_with("info "), "unexpected '{line}'");
+            lines.push(line);
+            if done {
+                return lines;
+            }
+        }
+    }
+
+    /// Nothing more was sent
+    fn expect_silence(&self) {
+        self.send("isready");
+        self.expect(&["readyok"]);
+    }
+}
+
+/// Value following `key` in an info line
+fn field<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let mut tokens = line.split_whitespace();
+    tokens.find(|&token| token == key)?;
+    tokens.next()
+}
+
+fn best_move(line: &str) -> UciMove {
+    field(line, "bestmove").unwrap().parse().unwrap()
+}
+
+#[test]
+fn handshake() {
+    let session = Session::start();
+
+    session.send("uci");
+    let options: Vec<String> = OPTIONS.iter().map(|option| option.to_string()).collect();
+    session.expect(&[
+        "id name kaksic",
+        &format!("id author {}", env!("CARGO_PKG_AUTHORS")),
+    ]);
+    session.expect(&options.iter().map(String::as_str).collect::<Vec<_>>());
+    session.expect(&["uciok"]);
+
+    session.send("isready");
+    session.expect(&["readyok"]);
+    session.quit();
+}
+
+#[test]
+fn search_to_depth() {
+    let session = Session::start();
+
+    session.send("ucinewgame");
+    session.send("position startpos moves e2e4");
+    let start = Instant::now();
+    session.send("go depth 2");
+    let lines = session.search_output(SEARCH_TIMEOUT);
+    assert!(
+        start.elapsed() < Duration::from_secs(2),
+        "{:?}",
+        start.elapsed()
+    );
+
+    // One line per iteration, then a legal reply
+    let depths: Vec<&str> = lines[..lines.len() - 1]
+        .iter()
+        .map(|line| field(line, "depth").unwrap())
+        .collect();
+    assert_eq!(depths, ["1", "2"]);
+    let e4: UciMove = "e2e4".parse().unwrap();
+    let position = Chess::default()
+        .play(e4.to_move(&Chess::default()).unwrap())
+        .unwrap();
+    assert!(best_move(lines.last().unwrap()).to_move(&position).is_ok());
+
+    session.expect_silence();
+    session.quit();
+}
+
+#[test]
+fn ready_while_searching_and_one_best_move_after_stop() {
+    let session = Session::start();
+
+    session.send("position startpos");
+    let start = Instant::now();
+    // Far too deep to end by itself
+    session.send("go depth 20");
+
+    // The controller answers while the searcher is busy
+    thread::sleep(Duration::from_millis(100));
+    session.send("isready");
+    let mut line = session.next_line(REPLY_TIMEOUT);
+    while line.starts_with("info ") {
+        line = session.next_line(REPLY_TIMEOUT);
+    }
+    assert_eq!(line, "readyok");
+    assert!(start.elapsed() < Duration::from_millis(600));
+
+    session.send("stop");
+    let lines = session.search_output(REPLY_TIMEOUT);
+    assert!(best_move(lines.last().unwrap())
+        .to_move(&Chess::default())
+        .is_ok());
+
+    session.expect_silence();
+    session.quit();
+}
+
+#[test]
+fn stop_and_quit_interrupt_the_search() {
+    let session = Session::start();
+
+    // Stopped in the middle of an iteration, the move of the last complete one is played
+    session.send("position startpos");
+    session.send("go infinite");
+    thread::sleep(Duration::from_millis(300));
+    session.send("stop");
+    let lines = session.search_output(REPLY_TIMEOUT);
+    assert!(best_move(lines.last().unwrap())
+        .to_move(&Chess::default())
+        .is_ok());
+
+    session.send("setoption name SearchAlgorithm value MCTS");
+    session.send("go depth 20");
+    thread::sleep(Duration::from_millis(300));
+    session.send("stop");
+    let lines = session.search_output(REPLY_TIMEOUT);
+    assert!(best_move(lines.last().unwrap())
+        .to_move(&Chess::default())
+        .is_ok());
+
+    session.send("setoption name SearchAlgorithm value Negamax");
+    session.send("go depth 20");
+    thread::sleep(Duration::from_millis(300));
+    session.quit();
+}
+
+#[test]
+fn info_reports_time_nps_and_hashfull() {
+    let session = Session::start();
+
+    session.send("position startpos");
+    session.send("go depth 3");
+    let lines = session.search_output(SEARCH_TIMEOUT);
+
+    // Nodes count from the start of the search, like the time they are divided by
+    let mut last_nodes = 0;
+    for line in lines.iter().filter(|line| field(line, "nodes").is_some()) {
+        let number = |key: &str| -> u64 {
+            field(line, key)
+                .unwrap_or_else(|| panic!("no {key} in '{line}'"))
+                .parse()
+                .unwrap()
+        };
+        let nodes = number("nodes");
+        assert!(nodes > last_nodes, "{line}");
+        assert_eq!(
+            number("nps"),
+            nodes * 1000 / number("time").max(1),
+            "{line}"
+        );
+        assert!(number("hashfull") <= 1000, "{line}");
+        last_nodes = nodes;
+    }
+    assert!(last_nodes > 0);
+
+    session.quit();
+}
+
+#[test]
+fn currmove_waits_and_is_throttled() {
+    let session = Session::start();
+
+    session.send("position startpos");
+    let start = Instant::now();
+    session.send("go depth 8");
+    let mut currmoves = Vec::new();
+    while start.elapsed() < Duration::from_millis(2500) {
+        if let Ok(line) = session.output.recv_timeout(Duration::from_millis(20)) {
+            if field(&line, "currmove").is_some() {
+                currmoves.push(start.elapsed());
+            }
+        }
+    }
+    session.send("stop");
+    session.search_output(SEARCH_TIMEOUT);
+
+    // Not in the first second, then at most every 100 ms, less some slack for the delivery
+    assert!(!currmoves.is_empty());
+    assert!(currmoves[0] >= Duration::from_millis(1000), "{currmoves:?}");
+    for pair in currmoves.windows(2) {
+        assert!(
+            pair[1] - pair[0] >= Duration::from_millis(50),
+            "{currmoves:?}"
+        );
+    }
+
+    session.quit();
+}
+
+#[test]
+fn rejected_position_keeps_the_previous_one() {
+    let session = Session::start();
+
+    session.send("position startpos moves e2e4");
+    session.send("position startpos moves e2e5");
+    let reply = session.next_line(REPLY_TIMEOUT);
+    assert!(
+        reply.starts_with("info string Rejected position: "),
+        "{reply}"
+    );
+
+    // Still searching the position after 1. e4
+    session.send("go depth 1");
+    let lines = session.search_output(SEARCH_TIMEOUT);
+    let mv = best_move(lines.last().unwrap()).to_string();
+    assert!(mv.ends_with('6') || mv.ends_with('5'), "{mv}");
+
+    session.quit();
+}
+
+#[test]
+fn variant_rules_apply_to_position_and_go() {
+    let session = Session::start();
+
+    // The king wins by reaching the center
+    session.send("setoption name UCI_Variant value kingofthehill");
+    session.send("position fen 4k3/8/8/8/8/2K5/1r6/8 w - - 0 1");
+    session.send("go depth 3");
+    let lines = session.search_output(SEARCH_TIMEOUT);
+    assert_eq!(lines.last().unwrap(), "bestmove c3d4");
+
+    // Drops are read in the moves and played by the engine
+    session.send("setoption name UCI_Variant value crazyhouse");
+    session.send("position startpos moves e2e4 d7d5 e4d5 d8d5 P@e4");
+    session.expect_silence();
+    session.send("position fen 6k1/5ppp/8/8/8/8/8/6K1[R] w - - 0 1");
+    session.send("go depth 2");
+    let lines = session.search_output(SEARCH_TIMEOUT);
+    let mv = best_move(lines.last().unwrap()).to_string();
+    assert!(mv.starts_with("R@") && mv.ends_with('8'), "{mv}");
+
+    // Back to chess, where a drop is not a move
+    session.send("setoption name UCI_Variant value chess");
+    session.send("position startpos moves e2e4 d7d5 e4d5 d8d5 P@e4");
+    let reply = session.next_line(REPLY_TIMEOUT);
+    assert!(
+        reply.starts_with("info string Rejected position: "),
+        "{reply}"
+    );
+
+    session.quit();
+}
+
+#[test]
+fn d_shows_the_position() {
+    let session = Session::start();
+
+    session.send("position fen rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 moves e2e4 c7c5 g1f3");
+    session.send("d");
+    let after = parse_fen("rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2");
+    let key = after.zobrist_hash::<Zobrist64>(EnPassantMode::Legal).0;
+    session.expect(&[
+        " +---+---+---+---+---+---+---+---+",
+        " | r | n | b | q | k | b | n | r | 8",
+        " +---+---+---+---+---+---+---+---+",
+        " | p | p |   | p | p | p | p | p | 7",
+        " +---+---+---+---+---+---+---+---+",
+        " |   |   |   |   |   |   |   |   | 6",
+        " +---+---+---+---+---+---+---+---+",
+        " |   |   | p |   |   |   |   |   | 5",
+        " +---+---+---+---+---+---+---+---+",
+        " |   |   |   |   | P |   |   |   | 4",
+        " +---+---+---+---+---+---+---+---+",
+        " |   |   |   |   |   | N |   |   | 3",
+        " +---+---+---+---+---+---+---+---+",
+        " | P | P | P | P |   | P | P | P | 2",
+        " +---+---+---+---+---+---+---+---+",
+        " | R | N | B | Q | K | B |   | R | 1",
+        " +---+---+---+---+---+---+---+---+",
+        "   a   b   c   d   e   f   g   h",
+        "",
+        "Fen: rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2",
+        "Side to move: black",
+        "Castling: KQkq",
+        "En passant: -",
+        &format!("Key: {key:016X}"),
+        "Checkers:",
+    ]);
+    let moves = session.next_line(REPLY_TIMEOUT);
+    assert!(moves.starts_with("Legal moves (22): "), "{moves}");
+
+    // Captured pieces go to the pocket of the capturing side
+    session.send("setoption name UCI_Variant value crazyhouse");
+    session.send("position startpos moves e2e4 d7d5 e4d5 d8d5");
+    session.send("d");
+    let lines: Vec<String> = (0..28).map(|_| session.next_line(REPLY_TIMEOUT)).collect();
+    assert_eq!(
+        lines[19],
+        "Fen: rnb1kbnr/ppp1pppp/8/3q4/8/8/PPPP1PPP/RNBQKBNR[Pp] w KQkq - 0 3"
+    );
+    assert_eq!(lines[20], "Side to move: white");
+    assert_eq!(lines[25..27], ["Pocket (white): P", "Pocket (black): p"]);
+    assert!(lines[27].starts_with("Legal moves "), "{}", lines[27]);
+
+    session.expect_silence();
+    session.quit();
+}
+
+#[test]
+fn null_move_without_legal_moves() {
+    let session = Session::start();
+
+    let finished = [
+        // Checkmate
+        "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3",
+        // Stalemate
+        "7k/5Q2/6K1/8/8/8/8/8 b - - 0 1",
+    ];
+    for fen in finished {
+        session.send(&format!("position fen {fen}"));
+        for go in ["go depth 3", "go movetime 100", "go infinite"] {
+            session.send(go);
+            let lines = session.search_output(SEARCH_TIMEOUT);
+            assert_eq!(lines.last().unwrap(), "bestmove 0000", "{fen} {go}");
+        }
+    }
+
+    // Still searching normally afterwards
+    session.send("position startpos");
+    session.send("go depth 1");
+    let lines = session.search_output(SEARCH_TIMEOUT);
+    assert_ne!(lines.last().unwrap(), "bestmove 0000");
+
+    session.quit();
+}
+
+#[test]
+fn unknown_command() {
+    let session = Session::start();
+
+    session.send("foo bar");
+    session.expect(&["info string unknown command foo bar"]);
+    session.quit();
+}
+
+#[test]
+fn rejected_options_are_reported() {
+    let session = Session::start();
+
+    session.send("setoption name Hash value lots");
+    session.expect(&["info string Hash must be a number from 1 to 1024, got 'lots'"]);
+    session.send("setoption name Ponder value true");
+    session.expect(&["info string Unknown option 'Ponder'"]);
+
+    // Clamped into range, and searching still works
+    session.send("setoption name Threads value 4");
+    session.send("setoption name MultiPV value 500");
+    session.send("position startpos");
+    session.send("go depth 1");
+    let lines = session.search_output(SEARCH_TIMEOUT);
+    let multipv = lines
+        .iter()
+        .filter(|line| field(line, "multipv").is_some())
+        .count();
+    assert_eq!(multipv, Chess::default().legal_moves().len());
+
+    session.expect_silence();
+    session.quit();
+}
+
+#[test]
+fn eval_prints_the_trace_of_the_current_position() {
+    let session = Session::start();
+
+    session.send("position startpos moves e2e4 d7d5");
+    session.send("eval");
+    let mut position = Chess::default();
+    for mv in ["e2e4", "d7d5"] {
+        let mv: UciMove = mv.parse().unwrap();
+        position.play_unchecked(mv.to_move(&position).unwrap());
+    }
+    let table = eval::trace(&position).to_string();
+    let expected: Vec<&str> = table.lines().collect();
+    session.expect(&expected);
+    session.expect_silence();
+
+    session.quit();
+}
+
+#[test]
+fn show_wdl() {
+    let session = Session::start();
+
+    session.send("setoption name UCI_ShowWDL value true");
+    session.send("position startpos");
+    session.send("go depth 1");
+    let lines = session.search_output(SEARCH_TIMEOUT);
+
+    // Permille chances that add up, placed before the pv
+    let info = &lines[0];
+    let chances: Vec<u32> = info
+        .split_whitespace()
+        .skip_while(|&token| token != "wdl")
+        .skip(1)
+        .take(3)
+        .map(|chance| chance.parse().unwrap())
+        .collect();
+    assert_eq!(chances.len(), 3, "{info}");
+    assert_eq!(chances.iter().sum::<u32>(), 1000, "{info}");
+    assert!(
+        info.find(" wdl ").unwrap() < info.find(" pv ").unwrap(),
+        "{info}"
+    );
+
+    session.quit();
+}
+
+#[test]
+fn book_move_instead_of_search() {
+    // A book with a single move, 1. e4
+    let start = Chess::default();
+    let e4: UciMove = "e2e4".parse().unwrap();
+    let mut bytes = Vec::new();
+    bytes.extend(book::key(&start).to_be_bytes());
+    bytes.extend(book::encode_move(e4.to_move(&start).unwrap()).to_be_bytes());
+    bytes.extend(1u16.to_be_bytes());
+    bytes.extend(0u32.to_be_bytes());
+    let path = env::temp_dir().join(format!("kaksic-book-{}.bin", std::process::id()));
+    fs::write(&path, bytes).unwrap();
+
+    let session = Session::start();
+    session.send(&format!("setoption name BookFile value {}", path.display()));
+    session.send("setoption name OwnBook value true");
+    session.send("setoption name BookSelection value Best");
+    session.send("position startpos");
+    session.send("go depth 3");
+    let lines = session.search_output(REPLY_TIMEOUT);
+    assert_eq!(lines.len(), 2, "{lines:?}");
+    assert_eq!(field(&lines[0], "depth"), Some("0"), "{lines:?}");
+    assert_eq!(field(&lines[0], "pv"), Some("e2e4"), "{lines:?}");
+    assert_eq!(lines[1], "bestmove e2e4");
+
+    // Past the book depth the engine searches again
+    session.send("setoption name BookDepth value 0");
+    session.send("go depth 1");
+    let lines = session.search_output(SEARCH_TIMEOUT);
+    assert!(lines.len() > 1, "{lines:?}");
+
+    session.quit();
+    let _ = fs::remove_file(path);
+}
+
+#[test]
+fn missing_tablebases_are_reported() {
+    let session = Session::start();
+
+    session.send("setoption name SyzygyPath value /nonexistent/syzygy");
+    let reply = session.next_line(REPLY_TIMEOUT);
+    assert!(
+        reply.starts_with("info string Failed to load '/nonexistent/syzygy'"),
+        "{reply}"
+    );
+
+    // Searching goes on without them
+    session.send("go depth 1");
+    session.search_output(SEARCH_TIMEOUT);
+    session.quit();
+}
+
+#[test]
+fn closed_input_ends_the_session() {
+    let Session {
+        input,
+        controller,
+        searcher,
+        ..
+    } = Session::start();
+    drop(input);
+    finish(controller, searcher);
+}
@@ -0,0 +1,129 @@
+//! Searching chess variants, whose rules decide what a win is.
+
+use crossbeam_channel::unbounded;
+use kaksic::search::eval::Handcrafted;
+use kaksic::search::mcts::Mcts;
+use kaksic::search::{Limits, SearchState};
+use kaksic::SearchInfo;
+use shakmaty::fen::Fen;
+use shakmaty::variant::{Variant, VariantPosition};
+use shakmaty::{CastlingMode, Color, KnownOutcome, Move, Outcome, Position, Role, Square};
+
+fn parse_fen(variant: Variant, fen: &str) -> VariantPosition {
+    let setup = fen.parse::<Fen>().unwrap().into_setup();
+    VariantPosition::from_setup(variant, setup, CastlingMode::Standard).unwrap()
+}
+
+/// Best move and the score of the last line reported for it
+fn search(state: &mut SearchState, position: &VariantPosition, depth: u8) -> (Move, i32) {
+    let (info_tx, info_rx) = unbounded();
+    let best_move = state
+        .search(position, &Limits::depth(depth), &info_tx)
+        .unwrap();
+    drop(info_tx);
+    let score = info_rx
+        .iter()
+        .filter_map(|info| match info {
+            SearchInfo::Info { score, .. } => Some(score),
+            _ => None,
+        })
+        .last()
+        .unwrap();
+    (best_move, score)
+}
+
+/// Best moves and scores of both algorithms
+fn best_moves(position: &VariantPosition, depth: u8) -> [(Move, i32); 2] {
+    let mut negamax = SearchState::new(Box::new(Handcrafted::default()));
+    let mut mcts = SearchState::new(Box::new(Handcrafted::default()));
+    mcts.set_algorithm(Box::new(Mcts));
+    [
+        search(&mut negamax, position, depth),
+        search(&mut mcts, position, depth),
+    ]
+}
+
+/// Negamax scores a win as the highest score, MCTS as the value of a certain win
+fn is_win(score: i32) -> bool {
+    score >= 1500
+}
+
+fn white_wins(position: &VariantPosition) -> bool {
+    position.outcome()
+        == Outcome::Known(KnownOutcome::Decisive {
+            winner: Color::White,
+        })
+}
+
+#[test]
+fn king_walks_to_the_hill() {
+    // Taking the rook would be good in chess, the center wins right away
+    let position = parse_fen(Variant::KingOfTheHill, "4k3/8/8/8/8/2K5/1r6/8 w - - 0 1");
+    for (mv, score) in best_moves(&position, 3) {
+        assert_eq!(mv.to(), Square::D4, "{mv}");
+        assert!(is_win(score), "{mv} {score}");
+        let mut after = position.clone();
+        after.play_unchecked(mv);
+        assert!(white_wins(&after), "{mv}");
+    }
+}
+
+#[test]
+fn crazyhouse_drops_are_played() {
+    let start = VariantPosition::new(Variant::Crazyhouse);
+    assert!(!start
+        .legal_moves()
+        .iter()
+        .any(|mv| matches!(mv, Move::Put { .. })));
+
+    // A rook in hand mates on the back rank
+    let position = parse_fen(Variant::Crazyhouse, "6k1/5ppp/8/8/8/8/8/6K1[R] w - - 0 1");
+    assert!(position.legal_moves().contains(&Move::Put {
+        role: Role::Rook,
+        to: Square::A8,
+    }));
+    for (mv, score) in best_moves(&position, 2) {
+        assert!(
+            matches!(
+                mv,
+                Move::Put {
+                    role: Role::Rook,
+                    ..
+                }
+            ),
+            "{mv}"
+        );
+        assert!(is_win(score), "{mv} {score}");
+        let mut after = position.clone();
+        after.play_unchecked(mv);
+        assert!(after.is_checkmate(), "{mv}");
+    }
+}
+
+#[test]
+fn antichess_without_pieces_is_a_win() {
+    // Offering the last rook, which must be taken, wins
+    let position = parse_fen(Variant::Antichess, "1r6/8/8/8/8/8/8/R7 w - - 0 1");
+    for (mv, score) in best_moves(&position, 3) {
+        assert!(is_win(score), "{mv} {score}");
+        let mut after = position.clone();
+        after.play_unchecked(mv);
+        let replies = after.legal_moves();
+        assert_eq!(replies.len(), 1, "{mv}");
+        after.play_unchecked(replies[0]);
+        assert!(white_wins(&after), "{mv}");
+    }
+}
+
+#[test]
+fn third_check_wins() {
+    // One check left to give, worth more than the queen
+    let position = parse_fen(Variant::ThreeCheck, "4k3/8/8/8/3q4/8/8/3QK3 w - - 1+3 0 1");
+    for (mv, score) in best_moves(&position, 2) {
+        assert!(is_win(score), "{mv} {score}");
+        let mut after = position.clone();
+        after.play_unchecked(mv);
+        assert!(after.is_check(), "{mv}");
+        assert!(white_wins(&after), "{mv}");
+    }
+}
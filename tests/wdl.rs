@@ -0,0 +1,47 @@
+//! Properties of the fitted win/draw/loss model.
+
+mod common;
+
+use common::parse_fen;
+use kaksic::search::wdl::{self, WDL_MODEL};
+use shakmaty::Chess;
+
+fn total(score: i32, material: u32) -> u16 {
+    let wdl = WDL_MODEL.wdl(score, material);
+    wdl.win + wdl.draw + wdl.loss
+}
+
+#[test]
+fn chances_add_up() {
+    for material in [0, 20, 40, 78, 100] {
+        for score in [i32::MIN, -5000, -300, 0, 300, 5000, i32::MAX] {
+            assert_eq!(total(score, material), 1000, "{score} {material}");
+        }
+    }
+}
+
+#[test]
+fn symmetric_and_monotonic() {
+    for material in [20, 40, 78] {
+        let even = WDL_MODEL.wdl(0, material);
+        assert_eq!(even.win, even.loss);
+
+        let mut last = WDL_MODEL.wdl(-1000, material);
+        for score in (-950..=1000).step_by(50) {
+            let wdl = WDL_MODEL.wdl(score, material);
+            assert!(
+                wdl.win >= last.win && wdl.loss <= last.loss,
+                "{score} {material}"
+            );
+            last = wdl;
+        }
+        assert!(last.win > even.win, "{material}");
+    }
+}
+
+#[test]
+fn material_counts_both_sides() {
+    assert_eq!(wdl::material(&Chess::default()), 78);
+    let position = parse_fen("4k3/8/8/8/8/8/4P3/R3K3 w - - 0 1");
+    assert_eq!(wdl::material(&position), 6);
+}
@@ -0,0 +1,169 @@
+//! Scripted xboard sessions against the controller and searcher, with output captured line by line.
+
+mod common;
+
+use common::{Session, REPLY_TIMEOUT, SEARCH_TIMEOUT};
+use crossbeam_channel::{unbounded, Sender};
+use kaksic::bot::log::FileLogger;
+use kaksic::bot::xboard::XBoardController;
+use shakmaty::{Chess, Position};
+use shakmaty_uci::UciMove;
+use std::thread;
+use std::time::{Duration, Instant};
+
+impl Session<Sender<String>> {
+    fn start() -> Self {
+        let (input, line_rx) = unbounded();
+        Session::spawn(input, |cmd_tx, info_rx, lines| {
+            XBoardController::new(line_rx, cmd_tx, info_rx, lines, FileLogger::new("")).run()
+        })
+    }
+
+    /// The next line that isn't thinking output, which starts with the depth
+    fn next_reply(&self, timeout: Duration) -> String {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let line = self.next_line(deadline.saturating_duration_since(Instant::now()));
+            if !line.starts_with(|c: char| c.is_ascii_digit()) {
+                return line;
+            }
+        }
+    }
+
+    /// Nothing more was sent
+    fn expect_silence(&self) {
+        self.send("ping 99");
+        self.expect(&["pong 99"]);
+    }
+}
+
+/// Move of a `move` reply
+fn engine_move(line: &str) -> UciMove {
+    let mv = line
+        .strip_prefix("move ")
+        .unwrap_or_else(|| panic!("'{line}'"));
+    mv.parse().unwrap()
+}
+
+#[test]
+fn handshake() {
+    let session = Session::start();
+
+    session.send("xboard");
+    session.send("protover 2");
+    let features = session.next_line(REPLY_TIMEOUT);
+    assert!(features.starts_with("feature "), "{features}");
+    assert!(features.contains("usermove=1"), "{features}");
+    assert!(features.contains("analyze=1"), "{features}");
+    session.expect(&["feature done=1"]);
+
+    session.send("ping 1");
+    session.expect(&["pong 1"]);
+    session.quit();
+}
+
+#[test]
+fn answers_user_moves() {
+    let session = Session::start();
+
+    session.send("new");
+    session.send("sd 2");
+    session.send("usermove e2e4");
+    let reply = session.next_reply(SEARCH_TIMEOUT);
+    let e4: UciMove = "e2e4".parse().unwrap();
+    let position = Chess::default()
+        .play(e4.to_move(&Chess::default()).unwrap())
+        .unwrap();
+    assert!(engine_move(&reply).to_move(&position).is_ok(), "{reply}");
+
+    session.send("usermove e2e5");
+    session.expect(&["Illegal move: e2e5"]);
+    session.send("foo");
+    session.expect(&["Error (unknown command): foo"]);
+
+    session.expect_silence();
+    session.quit();
+}
+
+#[test]
+fn thinking_output_after_post() {
+    let session = Session::start();
+
+    session.send("new");
+    session.send("force");
+    session.send("sd 2");
+    session.send("post");
+    session.send("go");
+
+    // `ply score time nodes pv`, one line per depth
+    let mut thinking = Vec::new();
+    let mut line = session.next_line(SEARCH_TIMEOUT);
+    while !line.starts_with("move ") {
+        thinking.push(line);
+        line = session.next_line(SEARCH_TIMEOUT);
+    }
+    let depths: Vec<&str> = thinking
+        .iter()
+        .map(|line| line.split(' ').next().unwrap())
+        .collect();
+    assert_eq!(depths, ["1", "2"], "{thinking:?}");
+    assert!(engine_move(&line).to_move(&Chess::default()).is_ok());
+
+    session.expect_silence();
+    session.quit();
+}
+
+#[test]
+fn mate_is_claimed() {
+    let session = Session::start();
+
+    session.send("new");
+    session.send("force");
+    for mv in ["f2f3", "e7e5", "g2g4"] {
+        session.send(&format!("usermove {mv}"));
+    }
+    session.send("usermove d8h4");
+    session.expect(&["0-1 {Black mates}"]);
+
+    // Nothing to think about once the game is over
+    session.send("go");
+    session.expect_silence();
+    session.quit();
+}
+
+#[test]
+fn exit_and_undo_interrupt_the_analysis() {
+    let session = Session::start();
+
+    session.send("new");
+    session.send("force");
+    session.send("usermove e2e4");
+    session.send("analyze");
+    thread::sleep(Duration::from_millis(300));
+    session.send("undo");
+    thread::sleep(Duration::from_millis(300));
+    session.send("exit");
+
+    // Playing from the start position right away, the analyses are over
+    let start = Instant::now();
+    session.send("sd 1");
+    session.send("go");
+    let reply = session.next_reply(SEARCH_TIMEOUT);
+    assert!(start.elapsed() < REPLY_TIMEOUT, "{:?}", start.elapsed());
+    assert!(
+        engine_move(&reply).to_move(&Chess::default()).is_ok(),
+        "{reply}"
+    );
+
+    session.expect_silence();
+    session.quit();
+}
+
+#[test]
+fn quit_while_analysing() {
+    let session = Session::start();
+
+    session.send("analyze");
+    thread::sleep(Duration::from_millis(300));
+    session.quit();
+}